@@ -0,0 +1,414 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use eyre::{eyre, Result};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::{Commit, Window};
+
+/// Where to find commits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Use cached commits, only fetch what is missing
+    Normal,
+    /// Ignore cached commits, fetch everything again
+    Refresh,
+    /// Only use cached commits, never touch the network
+    Offline,
+}
+
+/// Persistent commit store under `<cache dir>/commits/<API host>`, one
+/// JSON file per repository, so GitHub Enterprise Server instances never share
+/// repositories with github.com.
+///
+/// Forges treat names case-insensitively, so files are named after the lowercase
+/// `owner/name`, whether it comes from the API or from `--org` as typed.
+pub struct CommitCache {
+    dir: PathBuf,
+}
+
+/// Bodies of GET responses with their validators, one JSON file per URL under
+/// `<cache dir>/http`, for conditional requests.
+pub struct HttpCache {
+    dir: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedResponse {
+    /// The URL this response belongs to, as file names are only a hash of it
+    #[serde(default)]
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// The `rel="next"` link of a paginated response
+    pub next: Option<String>,
+    pub body: String,
+}
+
+/// Cached commits of a single repository.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RepoCache {
+    /// The interval whose commits on the default branch are all present in `commits`
+    covered: Option<Window>,
+    /// Same as `covered`, for other branches
+    #[serde(default)]
+    branches: BTreeMap<String, Window>,
+    /// Commits keyed by SHA
+    commits: BTreeMap<String, Commit>,
+    /// Branches each commit of `commits` was fetched from, keyed by SHA, with
+    /// `None` for the default branch
+    #[serde(default)]
+    sources: BTreeMap<String, BTreeSet<Option<String>>>,
+}
+
+impl CommitCache {
+    /// The cache under `dir` of the repositories served by the REST API at `api_url`.
+    pub fn new(dir: &Path, api_url: &str) -> Result<Self> {
+        let url = Url::parse(api_url).map_err(|e| eyre!("Invalid API URL {api_url}: {e}"))?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| eyre!("API URL {api_url} has no host"))?;
+        let host = match url.port() {
+            Some(port) => format!("{host}-{port}"),
+            None => host.to_string(),
+        };
+
+        Ok(Self {
+            dir: dir.join("commits").join(host.to_lowercase()),
+        })
+    }
+
+    /// Lowercase full names of the cached repositories belonging to `owner`.
+    pub fn repos(&self, owner: &str) -> Result<Vec<String>> {
+        let owner = owner.to_lowercase();
+        let dir = self.dir.join(&owner);
+        if !dir.is_dir() {
+            return Ok(vec![]);
+        }
+
+        let mut res = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                    res.push(format!("{owner}/{name}"));
+                }
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Load the cache of `repo` (`owner/name`), an unreadable cache counts as empty.
+    pub fn load(&self, repo: &str) -> RepoCache {
+        let path = self.path(repo);
+        if !path.exists() {
+            return RepoCache::default();
+        }
+
+        match fs::read(&path)
+            .map_err(eyre::Report::from)
+            .and_then(|s| Ok(serde_json::from_slice::<RepoCache>(&s)?))
+        {
+            // Older caches do not know which branch their commits are on.
+            Ok(cache) if cache.sources.is_empty() && !cache.commits.is_empty() => {
+                debug!("Ignoring cache {} without branches", path.display());
+                RepoCache::default()
+            }
+            Ok(cache) => cache,
+            Err(e) => {
+                warn!("Ignoring broken cache {}: {e}", path.display());
+                RepoCache::default()
+            }
+        }
+    }
+
+    pub fn store(&self, repo: &str, cache: &RepoCache) -> Result<()> {
+        write_atomic(&self.path(repo), &serde_json::to_vec(cache)?)
+    }
+
+    fn path(&self, repo: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", repo.to_lowercase()))
+    }
+}
+
+impl HttpCache {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.join("http"),
+        }
+    }
+
+    pub fn load(&self, url: &str) -> Option<CachedResponse> {
+        let resp: CachedResponse = serde_json::from_slice(&fs::read(self.path(url)).ok()?).ok()?;
+        (resp.url == url).then_some(resp)
+    }
+
+    pub fn store(&self, url: &str, resp: &CachedResponse) -> Result<()> {
+        write_atomic(&self.path(url), &serde_json::to_vec(resp)?)
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}.json", fnv1a(url.as_bytes())))
+    }
+}
+
+impl RepoCache {
+    /// The interval that still has to be fetched to answer `window`, `None` if
+    /// the cache already covers it or nothing was pushed since it was filled.
+    ///
+    /// Anything pushed since then may bring commits dated before `covered.until`,
+    /// e.g. a merged branch forked before the last run, so the whole `window` is
+    /// fetched again and the SHA map drops the duplicates.
+    ///
+    /// `branch` is `None` for the default branch.
+    pub fn missing(
+        &self,
+        branch: Option<&str>,
+        window: Window,
+        pushed_at: Option<DateTime<Utc>>,
+    ) -> Option<Window> {
+        let covered = match branch {
+            Some(branch) => self.branches.get(branch).copied(),
+            None => self.covered,
+        };
+
+        match covered {
+            Some(covered)
+                if covered.since <= window.since
+                    && (window.until <= covered.until
+                        || pushed_at.is_some_and(|p| p <= covered.until)) =>
+            {
+                None
+            }
+            _ => Some(window),
+        }
+    }
+
+    /// Record the commits fetched for `fetched` on `branch`.
+    pub fn insert(&mut self, branch: Option<&str>, fetched: Window, commits: Vec<Commit>) {
+        // Keyed by SHA, so commits reachable from several branches are stored once.
+        for i in commits {
+            self.sources
+                .entry(i.sha.clone())
+                .or_default()
+                .insert(branch.map(|b| b.to_string()));
+            self.commits.insert(i.sha.clone(), i);
+        }
+
+        let covered = match branch {
+            Some(branch) => self.branches.get(branch).copied(),
+            None => self.covered,
+        };
+
+        let covered = match covered {
+            // Overlapping intervals merge, otherwise only the fresh one is known to be complete.
+            Some(covered) if covered.since <= fetched.until && fetched.since <= covered.until => {
+                Window {
+                    since: covered.since.min(fetched.since),
+                    until: covered.until.max(fetched.until),
+                }
+            }
+            _ => fetched,
+        };
+
+        match branch {
+            Some(branch) => {
+                self.branches.insert(branch.to_string(), covered);
+            }
+            None => self.covered = Some(covered),
+        }
+    }
+
+    /// Branches with cached commits, as [`Self::into_commits`] takes them.
+    pub fn branches(&self) -> Vec<Option<String>> {
+        self.branches.keys().cloned().map(Some).collect()
+    }
+
+    /// Cached commits committed inside `window` on any of `branches`, `None`
+    /// standing for the default branch.
+    pub fn into_commits(self, window: Window, branches: &[Option<String>]) -> Vec<Commit> {
+        let sources = self.sources;
+        self.commits
+            .into_values()
+            .filter(|i| {
+                sources
+                    .get(&i.sha)
+                    .is_some_and(|s| branches.iter().any(|b| s.contains(b)))
+            })
+            .filter(|i| {
+                i.commit
+                    .as_ref()
+                    .is_some_and(|c| window.contains(c.committer.date))
+            })
+            .collect()
+    }
+}
+
+/// `$XDG_CACHE_HOME/kpi`, or `~/.cache/kpi` without it.
+pub fn default_dir() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_CACHE_HOME").filter(|d| !d.is_empty()) {
+        return Ok(Path::new(&dir).join("kpi"));
+    }
+
+    let home = std::env::var_os("HOME")
+        .ok_or_else(|| eyre!("Neither $XDG_CACHE_HOME nor $HOME is set"))?;
+
+    Ok(Path::new(&home).join(".cache").join("kpi"))
+}
+
+/// Write then rename, so an interrupted run never leaves a truncated cache behind.
+fn write_atomic(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(tmp, path)?;
+
+    Ok(())
+}
+
+/// 64-bit FNV-1a, a file name for URLs that stays stable across builds.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::{RepoAuthor, RepoCommit};
+
+    fn day(d: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 6, d, 0, 0, 0).unwrap()
+    }
+
+    fn window(since: u32, until: u32) -> Window {
+        Window {
+            since: day(since),
+            until: day(until),
+        }
+    }
+
+    #[test]
+    fn refetches_the_window_after_a_push() {
+        let mut cache = RepoCache::default();
+        cache.insert(None, window(1, 10), vec![]);
+
+        assert!(cache.missing(None, window(1, 10), Some(day(12))).is_none());
+        assert!(cache.missing(None, window(1, 20), Some(day(9))).is_none());
+
+        // A merge on the 12th may bring commits dated before the 10th.
+        let missing = cache.missing(None, window(1, 20), Some(day(12))).unwrap();
+        assert_eq!((missing.since, missing.until), (day(1), day(20)));
+    }
+
+    fn commit(sha: &str, d: u32) -> Commit {
+        let identity = || RepoAuthor {
+            name: String::new(),
+            email: String::new(),
+            date: day(d),
+        };
+
+        Commit {
+            sha: sha.to_string(),
+            commit: Some(RepoCommit {
+                author: identity(),
+                committer: identity(),
+                message: String::new(),
+            }),
+            author: None,
+            committer: None,
+        }
+    }
+
+    /// The default branch and `main` share a commit, `topic` has its own.
+    fn filled() -> RepoCache {
+        let mut cache = RepoCache::default();
+        cache.insert(None, window(1, 10), vec![commit("a", 2)]);
+        cache.insert(Some("main"), window(1, 10), vec![commit("a", 2)]);
+        cache.insert(Some("topic"), window(1, 10), vec![commit("b", 3)]);
+        cache
+    }
+
+    fn shas(commits: Vec<Commit>) -> Vec<String> {
+        commits.into_iter().map(|c| c.sha).collect()
+    }
+
+    #[test]
+    fn keeps_branches_apart() {
+        assert_eq!(shas(filled().into_commits(window(1, 10), &[None])), ["a"]);
+
+        let cache = filled();
+        let branches = cache.branches();
+        assert_eq!(
+            branches,
+            [Some("main".to_string()), Some("topic".to_string())]
+        );
+        assert_eq!(
+            shas(cache.into_commits(window(1, 10), &branches)),
+            ["a", "b"]
+        );
+    }
+
+    #[test]
+    fn checks_the_url_of_cached_responses() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = HttpCache::new(dir.path());
+        let resp = CachedResponse {
+            url: "https://example.com/a".to_string(),
+            etag: Some("v1".to_string()),
+            last_modified: None,
+            next: None,
+            body: "[]".to_string(),
+        };
+
+        // As if both URLs hashed to the same file.
+        cache.store("https://example.com/b", &resp).unwrap();
+        assert!(cache.load("https://example.com/b").is_none());
+
+        cache.store("https://example.com/a", &resp).unwrap();
+        assert!(cache.load("https://example.com/a").is_some());
+    }
+
+    #[test]
+    fn finds_repos_regardless_of_case() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = CommitCache::new(dir.path(), "https://api.github.com").unwrap();
+        cache
+            .store("Case-Test/Repo", &RepoCache::default())
+            .unwrap();
+
+        assert_eq!(cache.repos("case-test").unwrap(), ["case-test/repo"]);
+        assert_eq!(cache.repos("CASE-TEST").unwrap(), ["case-test/repo"]);
+    }
+
+    #[test]
+    fn keeps_hosts_apart() {
+        let dir = tempfile::tempdir().unwrap();
+        let github = CommitCache::new(dir.path(), "https://api.github.com").unwrap();
+        let enterprise = CommitCache::new(dir.path(), "https://GitHub.Example.com/api/v3").unwrap();
+        let mock = CommitCache::new(dir.path(), "http://127.0.0.1:8765/api/v3").unwrap();
+        github
+            .store("hosts-test/repo", &RepoCache::default())
+            .unwrap();
+
+        assert_eq!(github.repos("hosts-test").unwrap(), ["hosts-test/repo"]);
+        assert!(enterprise.repos("hosts-test").unwrap().is_empty());
+        assert!(mock.repos("hosts-test").unwrap().is_empty());
+        assert!(enterprise.dir.ends_with("commits/github.example.com"));
+        assert!(mock.dir.ends_with("commits/127.0.0.1-8765"));
+    }
+}
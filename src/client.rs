@@ -0,0 +1,575 @@
+use std::{
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use eyre::{bail, eyre, Result};
+use indicatif::ProgressBar;
+use reqwest::{
+    header::{
+        HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, LINK,
+        RETRY_AFTER,
+    },
+    Client, RequestBuilder, Response, StatusCode, Url,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use tracing::{debug, warn};
+
+use crate::{
+    cache::{CacheMode, CachedResponse, HttpCache},
+    update_pb,
+};
+
+/// The body of a GraphQL response.
+#[derive(Deserialize, Debug)]
+pub struct GraphqlResponse {
+    #[serde(default)]
+    pub data: serde_json::Value,
+    #[serde(default)]
+    pub errors: Vec<GraphqlError>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GraphqlError {
+    pub message: String,
+    /// The field the error is about, starting with its alias
+    #[serde(default)]
+    pub path: Vec<serde_json::Value>,
+}
+
+/// Give up after this many retries of a single request.
+const MAX_RETRIES: u32 = 5;
+/// Cap of the exponential backoff between retries of transient failures.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// GitHub asks to wait at least one minute on a secondary rate limit without `Retry-After`.
+const SECONDARY_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// A client for forge REST APIs which waits out rate limits, retries transient
+/// failures and revalidates cached responses with conditional requests.
+///
+/// Pagination follows `Link` headers, which GitHub, GitLab and Gitea all send.
+pub struct ApiClient {
+    client: Client,
+    token: String,
+    cache: HttpCache,
+    mode: CacheMode,
+    pb: Option<ProgressBar>,
+}
+
+impl ApiClient {
+    /// A client caching responses under `cache_dir`.
+    pub fn new(
+        token: String,
+        mode: CacheMode,
+        cache_dir: &Path,
+        pb: Option<ProgressBar>,
+    ) -> Result<Self> {
+        Ok(Self {
+            client: Client::builder().user_agent("aosc-kpi").build()?,
+            token,
+            cache: HttpCache::new(cache_dir),
+            mode,
+            pb,
+        })
+    }
+
+    /// Send a GET request, returning an error for non-success statuses that
+    /// remain after retrying.
+    async fn get(&self, url: &str, headers: HeaderMap) -> Result<Response> {
+        self.send(url, || self.client.get(url).headers(headers.clone()))
+            .await
+    }
+
+    /// Run a GraphQL query against `url`. Fails only without any `data`, errors
+    /// of single fields are left to the caller next to the data of the others.
+    pub async fn graphql(&self, url: &str, query: &str) -> Result<GraphqlResponse> {
+        if self.mode == CacheMode::Offline {
+            bail!("GraphQL queries are not available offline");
+        }
+
+        let body = serde_json::json!({ "query": query });
+        let resp = self
+            .send(url, || self.client.post(url).json(&body))
+            .await?
+            .json::<GraphqlResponse>()
+            .await?;
+
+        if resp.data.is_null() {
+            let errors = resp
+                .errors
+                .iter()
+                .map(|e| e.message.as_str())
+                .collect::<Vec<_>>();
+            bail!("GraphQL query failed: {}", errors.join("; "));
+        }
+
+        Ok(resp)
+    }
+
+    /// Send the request built by `build`, retrying it as needed.
+    async fn send(&self, url: &str, build: impl Fn() -> RequestBuilder) -> Result<Response> {
+        let mut attempt = 0;
+
+        loop {
+            let resp = build()
+                .header("Authorization", format!("Bearer {}", self.token))
+                .send()
+                .await;
+
+            let wait = match resp {
+                Ok(resp) => {
+                    self.report_quota(resp.headers());
+
+                    let status = resp.status();
+                    if status.is_success() || attempt >= MAX_RETRIES {
+                        return Ok(resp.error_for_status()?);
+                    }
+
+                    if status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS {
+                        // Keep the error around, checking for a secondary rate limit consumes the body.
+                        let err = resp.error_for_status_ref().unwrap_err();
+                        match rate_limit_wait(resp).await {
+                            Some(wait) => {
+                                self.notify(format!(
+                                    "Rate limited on {url}, waiting {}s ...",
+                                    wait.as_secs()
+                                ));
+                                wait
+                            }
+                            None => return Err(err.into()),
+                        }
+                    } else if status.is_server_error() {
+                        let wait = backoff(attempt);
+                        warn!("{url} returned {status}, retrying in {:?}", wait);
+                        wait
+                    } else {
+                        return Ok(resp.error_for_status()?);
+                    }
+                }
+                Err(e) if attempt < MAX_RETRIES && (e.is_timeout() || e.is_connect()) => {
+                    let wait = backoff(attempt);
+                    warn!("Failed to request {url}: {e}, retrying in {:?}", wait);
+                    wait
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            tokio::time::sleep(wait).await;
+            attempt += 1;
+        }
+    }
+
+    /// Fetch every page of a GitHub list endpoint by following the `Link: rel="next"` header.
+    ///
+    /// `take` is called on each item in order; returning `false` stops the pagination
+    /// and drops that item, which lets callers bail out early on sorted lists.
+    pub async fn get_paginated<T, F>(
+        &self,
+        url: String,
+        pb: Option<&ProgressBar>,
+        mut take: F,
+    ) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+        F: FnMut(&T) -> Result<bool>,
+    {
+        let mut next = Some(url);
+        let mut page = 1;
+        let mut items = vec![];
+
+        while let Some(url) = next {
+            update_pb(pb, format!("Getting {} page: {}", url, page));
+
+            let resp = self.get_cached(&url).await?;
+            next = resp.next;
+
+            for i in serde_json::from_str::<Vec<T>>(&resp.body)? {
+                if !take(&i)? {
+                    return Ok(items);
+                }
+
+                items.push(i);
+            }
+
+            page += 1;
+        }
+
+        Ok(items)
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        Ok(serde_json::from_str(&self.get_cached(url).await?.body)?)
+    }
+
+    /// GET `url`, answering from the HTTP cache when GitHub reports it unchanged.
+    ///
+    /// 304 responses do not count against the rate limit, so repeated runs over
+    /// unchanged data cost next to nothing.
+    async fn get_cached(&self, url: &str) -> Result<CachedResponse> {
+        let cached = match self.mode {
+            CacheMode::Refresh => None,
+            _ => self.cache.load(url),
+        };
+
+        if self.mode == CacheMode::Offline {
+            return cached.ok_or_else(|| eyre!("{url} is not cached, cannot fetch it offline"));
+        }
+
+        let mut headers = HeaderMap::new();
+        if let Some(cached) = &cached {
+            if let Some(etag) = cached
+                .etag
+                .as_deref()
+                .and_then(|v| HeaderValue::from_str(v).ok())
+            {
+                headers.insert(IF_NONE_MATCH, etag);
+            }
+
+            if let Some(lm) = cached
+                .last_modified
+                .as_deref()
+                .and_then(|v| HeaderValue::from_str(v).ok())
+            {
+                headers.insert(IF_MODIFIED_SINCE, lm);
+            }
+        }
+
+        let resp = self.get(url, headers).await?;
+
+        if resp.status() == StatusCode::NOT_MODIFIED {
+            if let Some(cached) = cached {
+                debug!("{url} is not modified");
+                return Ok(cached);
+            }
+        }
+
+        let headers = resp.headers();
+        let etag = header_str(headers, ETAG.as_str()).map(|s| s.to_string());
+        let last_modified = header_str(headers, LAST_MODIFIED.as_str()).map(|s| s.to_string());
+        let next = next_page_url(headers);
+
+        let resp = CachedResponse {
+            url: url.to_string(),
+            etag,
+            last_modified,
+            next,
+            body: resp.text().await?,
+        };
+
+        if (resp.etag.is_some() || resp.last_modified.is_some()) && !embeds_window(url) {
+            if let Err(e) = self.cache.store(url, &resp) {
+                warn!("Failed to cache {url}: {e}");
+            }
+        }
+
+        Ok(resp)
+    }
+
+    fn report_quota(&self, headers: &HeaderMap) {
+        let Some(remaining) = rate_limit_header(headers, "remaining") else {
+            return;
+        };
+
+        match &self.pb {
+            Some(pb) => pb.set_prefix(format!("[quota: {remaining}]")),
+            None => debug!("Remaining rate limit quota: {remaining}"),
+        }
+    }
+
+    fn notify(&self, msg: String) {
+        match &self.pb {
+            Some(pb) => pb.println(msg),
+            None => warn!("{msg}"),
+        }
+    }
+}
+
+/// How long to wait before retrying a 403 or 429 response, `None` if it is
+/// not caused by a rate limit.
+async fn rate_limit_wait(resp: Response) -> Option<Duration> {
+    let headers = resp.headers();
+
+    if let Some(secs) = header_str(headers, RETRY_AFTER.as_str()).and_then(|s| s.parse().ok()) {
+        return Some(Duration::from_secs(secs));
+    }
+
+    if rate_limit_header(headers, "remaining") == Some("0") {
+        let reset = rate_limit_header(headers, "reset")?.parse::<u64>().ok()?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+        // One extra second as the reset time is truncated.
+        return Some(Duration::from_secs(reset.saturating_sub(now) + 1));
+    }
+
+    let status = resp.status();
+    let body = resp.text().await.unwrap_or_default();
+    if status == StatusCode::TOO_MANY_REQUESTS || body.contains("secondary rate limit") {
+        return Some(SECONDARY_RATE_LIMIT_WAIT);
+    }
+
+    None
+}
+
+/// Exponential backoff with jitter: a random duration between half and all of
+/// `2^attempt` seconds, capped at [`MAX_BACKOFF`].
+fn backoff(attempt: u32) -> Duration {
+    let max = Duration::from_secs(1 << attempt.min(6)).min(MAX_BACKOFF);
+    // Sub-second clock noise is random enough to keep parallel tasks apart.
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    let jitter = f64::from(nanos) / 1_000_000_000.0;
+
+    max / 2 + max.mul_f64(jitter / 2.0)
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name)?.to_str().ok()
+}
+
+/// GitHub and Gitea send `X-RateLimit-*` headers, GitLab sends `RateLimit-*`.
+fn rate_limit_header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    header_str(headers, &format!("x-ratelimit-{name}"))
+        .or_else(|| header_str(headers, &format!("ratelimit-{name}")))
+}
+
+/// Whether `url` filters by a `since` or `until` date. With `--days` these
+/// change on every run, so caching them would only pile up files never read again.
+fn embeds_window(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|url| {
+        url.query_pairs()
+            .any(|(key, _)| key == "since" || key == "until")
+    })
+}
+
+/// Extract the `rel="next"` target from a GitHub `Link` header.
+fn next_page_url(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(LINK)?
+        .to_str()
+        .ok()?
+        .split(',')
+        .find_map(|link| {
+            let (url, params) = link.split_once(';')?;
+            params
+                .split(';')
+                .any(|p| p.trim() == r#"rel="next""#)
+                .then(|| {
+                    url.trim()
+                        .trim_start_matches('<')
+                        .trim_end_matches('>')
+                        .to_string()
+                })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::{
+        matchers::{header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    fn client(dir: &Path) -> ApiClient {
+        ApiClient::new("token".to_string(), CacheMode::Normal, dir, None).unwrap()
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(502))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(200).set_body_json([1, 2]))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let res = client(dir.path())
+            .get_json::<Vec<u32>>(&format!("{}/flaky", server.uri()))
+            .await
+            .unwrap();
+
+        assert_eq!(res, [1, 2]);
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn waits_for_exhausted_quota() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/limited"))
+            .respond_with(
+                ResponseTemplate::new(403)
+                    .insert_header("x-ratelimit-remaining", "0")
+                    .insert_header("x-ratelimit-reset", now().to_string().as_str()),
+            )
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/limited"))
+            .respond_with(ResponseTemplate::new(200).set_body_json("ok"))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let res = client(dir.path())
+            .get_json::<String>(&format!("{}/limited", server.uri()))
+            .await
+            .unwrap();
+
+        assert_eq!(res, "ok");
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn honors_retry_after() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/busy"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "0"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/busy"))
+            .respond_with(ResponseTemplate::new(200).set_body_json("ok"))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let res = client(dir.path())
+            .get_json::<String>(&format!("{}/busy", server.uri()))
+            .await
+            .unwrap();
+
+        assert_eq!(res, "ok");
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_forbidden() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/private"))
+            .respond_with(ResponseTemplate::new(403).set_body_string("Must have admin rights"))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let res = client(dir.path())
+            .get_json::<String>(&format!("{}/private", server.uri()))
+            .await;
+
+        assert!(res.is_err());
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn revalidates_cached_responses() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/members"))
+            .and(header("if-none-match", r#""v1""#))
+            .respond_with(ResponseTemplate::new(304))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/members"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", r#""v1""#)
+                    .set_body_json(["alice"]),
+            )
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let gh = client(dir.path());
+        let url = format!("{}/members", server.uri());
+        let first = gh.get_json::<Vec<String>>(&url).await.unwrap();
+        let second = gh.get_json::<Vec<String>>(&url).await.unwrap();
+
+        assert_eq!(first, ["alice"]);
+        assert_eq!(second, ["alice"]);
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].headers.get("if-none-match").is_none());
+    }
+
+    #[tokio::test]
+    async fn does_not_cache_windowed_urls() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/commits"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", r#""v1""#)
+                    .set_body_json(["abc"]),
+            )
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let gh = client(dir.path());
+        let windowed = format!("{}/commits?since=2026-06-01T00:00:00Z", server.uri());
+        gh.get_json::<Vec<String>>(&windowed).await.unwrap();
+        assert!(gh.cache.load(&windowed).is_none());
+
+        let plain = format!("{}/commits", server.uri());
+        gh.get_json::<Vec<String>>(&plain).await.unwrap();
+        assert_eq!(gh.cache.load(&plain).unwrap().url, plain);
+    }
+
+    #[test]
+    fn finds_next_page() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            LINK,
+            HeaderValue::from_static(
+                r#"<https://api.github.com/orgs/x/repos?page=1>; rel="prev", <https://api.github.com/orgs/x/repos?page=3>; rel="next""#,
+            ),
+        );
+
+        assert_eq!(
+            next_page_url(&headers).as_deref(),
+            Some("https://api.github.com/orgs/x/repos?page=3")
+        );
+        assert_eq!(next_page_url(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn reads_gitlab_rate_limit_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("ratelimit-remaining", HeaderValue::from_static("42"));
+
+        assert_eq!(rate_limit_header(&headers, "remaining"), Some("42"));
+        assert_eq!(rate_limit_header(&headers, "reset"), None);
+    }
+
+    #[test]
+    fn backoff_grows_and_is_capped() {
+        for attempt in 0..10 {
+            let max = Duration::from_secs(1 << attempt.min(6)).min(MAX_BACKOFF);
+            let wait = backoff(attempt);
+            assert!(max / 2 <= wait && wait <= max, "{attempt}: {wait:?}");
+        }
+    }
+}
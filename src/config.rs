@@ -0,0 +1,220 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::{bail, eyre, Result};
+use serde::{Deserialize, Deserializer};
+
+/// `kpi.toml`: settings shared by every run, and named profiles layered on top.
+///
+/// ```toml
+/// [defaults]
+/// org = "aosc-dev"
+/// thread = 8
+///
+/// [profiles.monthly]
+/// days = 31
+/// filter_org_user = true
+/// format = "markdown"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub defaults: Profile,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// Settings of a run, named like the command line options they stand for.
+/// Enums are spelled like on the command line too, e.g. `format = "markdown"`.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub source: Option<String>,
+    pub api_url: Option<String>,
+    /// One organization, or a list of them
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub org: Option<Vec<String>>,
+    pub repo: Option<Vec<String>>,
+    pub repos_dir: Option<PathBuf>,
+    pub days: Option<u64>,
+    /// RFC3339 or `YYYY-MM-DD`
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub format: Option<String>,
+    pub sort_by: Option<String>,
+    pub thread: Option<usize>,
+    pub filter_org_user: Option<bool>,
+    pub outside_collaborators: Option<bool>,
+    pub include_repo: Option<Vec<String>>,
+    pub exclude_repo: Option<Vec<String>>,
+    pub topic: Option<Vec<String>>,
+    pub no_forks: Option<bool>,
+    pub no_archived: Option<bool>,
+    pub no_templates: Option<bool>,
+    pub all_branches: Option<bool>,
+    pub pulls: Option<bool>,
+    pub issues: Option<bool>,
+    pub trailers: Option<Vec<String>>,
+    pub lines: Option<bool>,
+    pub exclude_path: Option<Vec<String>>,
+    pub max_lines_per_commit: Option<u64>,
+    pub bot: Option<Vec<String>>,
+    pub teams: Option<bool>,
+    pub team: Option<String>,
+    pub team_repos: Option<bool>,
+    /// Relative to the working directory, like on the command line
+    pub mailmap: Option<PathBuf>,
+}
+
+impl Config {
+    /// Load `path`, or else the first of `./kpi.toml` and
+    /// `$XDG_CONFIG_HOME/kpi/kpi.toml` that exists. `None` if there is none.
+    pub fn discover(path: Option<&Path>) -> Result<Option<Self>> {
+        if let Some(path) = path {
+            return Self::load(path).map(Some);
+        }
+
+        let mut candidates = vec![PathBuf::from("kpi.toml")];
+        if let Some(dir) = config_dir() {
+            candidates.push(dir.join("kpi").join("kpi.toml"));
+        }
+
+        match candidates.into_iter().find(|p| p.is_file()) {
+            Some(path) => Self::load(&path).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let s = fs::read_to_string(path)
+            .map_err(|e| eyre!("Failed to read {}: {e}", path.display()))?;
+
+        toml::from_str(&s).map_err(|e| eyre!("Failed to parse {}: {e}", path.display()))
+    }
+
+    /// The defaults, overridden by the profile `name` if given.
+    pub fn profile(&self, name: Option<&str>) -> Result<Profile> {
+        let Some(name) = name else {
+            return Ok(self.defaults.clone());
+        };
+
+        match self.profiles.get(name) {
+            Some(profile) => Ok(profile.clone().or(self.defaults.clone())),
+            None => bail!(
+                "No profile {name}, known profiles: {}",
+                self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+}
+
+impl Profile {
+    /// Settings of `self`, falling back to `other` for those it lacks.
+    pub fn or(self, other: Profile) -> Profile {
+        // The window is set as a whole, `days` of the defaults must not leak
+        // into a profile with `since`.
+        let (days, since) = if self.days.is_some() || self.since.is_some() {
+            (self.days, self.since)
+        } else {
+            (other.days, other.since)
+        };
+
+        Profile {
+            source: self.source.or(other.source),
+            api_url: self.api_url.or(other.api_url),
+            org: self.org.or(other.org),
+            repo: self.repo.or(other.repo),
+            repos_dir: self.repos_dir.or(other.repos_dir),
+            days,
+            since,
+            until: self.until.or(other.until),
+            format: self.format.or(other.format),
+            sort_by: self.sort_by.or(other.sort_by),
+            thread: self.thread.or(other.thread),
+            filter_org_user: self.filter_org_user.or(other.filter_org_user),
+            outside_collaborators: self.outside_collaborators.or(other.outside_collaborators),
+            include_repo: self.include_repo.or(other.include_repo),
+            exclude_repo: self.exclude_repo.or(other.exclude_repo),
+            topic: self.topic.or(other.topic),
+            no_forks: self.no_forks.or(other.no_forks),
+            no_archived: self.no_archived.or(other.no_archived),
+            no_templates: self.no_templates.or(other.no_templates),
+            all_branches: self.all_branches.or(other.all_branches),
+            pulls: self.pulls.or(other.pulls),
+            issues: self.issues.or(other.issues),
+            trailers: self.trailers.or(other.trailers),
+            lines: self.lines.or(other.lines),
+            exclude_path: self.exclude_path.or(other.exclude_path),
+            max_lines_per_commit: self.max_lines_per_commit.or(other.max_lines_per_commit),
+            bot: self.bot.or(other.bot),
+            teams: self.teams.or(other.teams),
+            team: self.team.or(other.team),
+            team_repos: self.team_repos.or(other.team_repos),
+            mailmap: self.mailmap.or(other.mailmap),
+        }
+    }
+}
+
+/// Accept `key = "value"` as a shorthand for `key = ["value"]`.
+fn one_or_many<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Vec<String>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(Option::<OneOrMany>::deserialize(d)?.map(|v| match v {
+        OneOrMany::One(s) => vec![s],
+        OneOrMany::Many(v) => v,
+    }))
+}
+
+fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+
+    std::env::var_os("HOME").map(|home| Path::new(&home).join(".config"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn falls_back_to_the_defaults() {
+        let config: Config = toml::from_str(
+            r#"
+[defaults]
+org = "aosc-dev"
+days = 31
+pulls = true
+
+[profiles.quarterly]
+since = "2026-04-01"
+pulls = false
+
+[profiles.weekly]
+org = ["aosc-dev", "aosc-tw"]
+"#,
+        )
+        .unwrap();
+
+        let quarterly = config.profile(Some("quarterly")).unwrap();
+        assert_eq!(quarterly.org.unwrap(), ["aosc-dev"]);
+        assert_eq!(quarterly.since.as_deref(), Some("2026-04-01"));
+        assert_eq!(quarterly.days, None);
+        assert_eq!(quarterly.pulls, Some(false));
+
+        let weekly = config.profile(Some("weekly")).unwrap();
+        assert_eq!(weekly.org.unwrap(), ["aosc-dev", "aosc-tw"]);
+        assert_eq!(weekly.days, Some(31));
+
+        assert!(config.profile(Some("monthly")).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+use eyre::Result;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+use crate::Repo;
+
+/// Decides which repositories of the organization are counted.
+pub struct RepoFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    topics: Vec<String>,
+    skip_forks: bool,
+    skip_archived: bool,
+    skip_templates: bool,
+}
+
+impl RepoFilter {
+    pub fn new(
+        include: &[String],
+        exclude: &[String],
+        topics: Vec<String>,
+        skip_forks: bool,
+        skip_archived: bool,
+        skip_templates: bool,
+    ) -> Result<Self> {
+        Ok(Self {
+            include: if include.is_empty() {
+                None
+            } else {
+                Some(build_globset(include)?)
+            },
+            exclude: build_globset(exclude)?,
+            topics,
+            skip_forks,
+            skip_archived,
+            skip_templates,
+        })
+    }
+
+    pub fn matches(&self, repo: &Repo) -> bool {
+        if (self.skip_forks && repo.fork)
+            || (self.skip_archived && repo.archived)
+            || (self.skip_templates && repo.is_template)
+        {
+            return false;
+        }
+
+        if !self.topics.is_empty() && !repo.topics.iter().any(|t| self.topics.contains(t)) {
+            return false;
+        }
+
+        self.matches_name(&repo.full_name)
+    }
+
+    /// Check only the name globs, against both `owner/name` and the bare name.
+    pub fn matches_name(&self, full_name: &str) -> bool {
+        let name = full_name
+            .split_once('/')
+            .map(|(_, name)| name)
+            .unwrap_or(full_name);
+        let is_match = |set: &GlobSet| set.is_match(name) || set.is_match(full_name);
+
+        self.include.as_ref().is_none_or(is_match) && !is_match(&self.exclude)
+    }
+}
+
+/// Decides which changed files count towards the lines of a commit.
+pub struct PathFilter {
+    exclude: GlobSet,
+}
+
+impl PathFilter {
+    pub fn new(exclude: &[String]) -> Result<Self> {
+        Ok(Self {
+            exclude: build_globset(exclude)?,
+        })
+    }
+
+    /// `*` also matches `/`, so `*.lock` excludes lockfiles in any directory.
+    pub fn matches(&self, path: &str) -> bool {
+        !self.exclude.is_match(path)
+    }
+}
+
+/// Recognizes automation accounts: GitHub Apps (`name[bot]`), users of type
+/// `Bot`, and logins or emails matching the configured globs.
+#[derive(Debug, Default)]
+pub struct BotFilter {
+    ignore: GlobSet,
+}
+
+impl BotFilter {
+    pub fn new(ignore: &[String]) -> Result<Self> {
+        Ok(Self {
+            ignore: build_globset(ignore)?,
+        })
+    }
+
+    pub fn is_bot(
+        &self,
+        login: Option<&str>,
+        email: Option<&str>,
+        user_type: Option<&str>,
+    ) -> bool {
+        user_type == Some("Bot")
+            || login.is_some_and(|l| l.ends_with("[bot]") || self.ignore.is_match(l))
+            || email.is_some_and(|e| e.contains("[bot]@") || self.ignore.is_match(e))
+    }
+}
+
+fn build_globset(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for i in patterns {
+        builder.add(Glob::new(i)?);
+    }
+
+    Ok(builder.build()?)
+}
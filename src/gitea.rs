@@ -0,0 +1,420 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use eyre::Result;
+use indicatif::ProgressBar;
+use reqwest::Url;
+use serde::Deserialize;
+
+use crate::{
+    cache::CacheMode,
+    client::ApiClient,
+    source::{dedup_repos, ContributionSource},
+    Author, Branch, Commit, Membership, Repo, Window,
+};
+
+/// Gitea caps pages at 50 items by default.
+const PAGE_SIZE: &str = "50";
+
+/// What a [`GiteaSource`] fetches.
+#[derive(Debug, Clone)]
+pub struct GiteaOptions {
+    pub orgs: Vec<String>,
+    /// Repositories (`owner/name`) counted besides those of `orgs`
+    pub repos: Vec<String>,
+    /// Base URL of the REST API, e.g. `https://codeberg.org/api/v1`
+    pub api_url: String,
+    /// Count commits on every branch instead of only the default branch
+    pub all_branches: bool,
+    pub mode: CacheMode,
+    /// Where responses are cached, usually [`crate::cache::default_dir`]
+    pub cache_dir: PathBuf,
+}
+
+/// Contributions to a Gitea or Forgejo organization.
+///
+/// Their API mirrors the GitHub one closely enough that commits, branches and
+/// users deserialize into the same types.
+pub struct GiteaSource {
+    gt: ApiClient,
+    options: GiteaOptions,
+    pb: Option<ProgressBar>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GiteaRepo {
+    url: String,
+    full_name: String,
+    updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    fork: bool,
+    #[serde(default)]
+    archived: bool,
+    #[serde(default)]
+    template: bool,
+    #[serde(default)]
+    topics: Vec<String>,
+}
+
+impl From<GiteaRepo> for Repo {
+    fn from(value: GiteaRepo) -> Self {
+        Repo {
+            url: value.url,
+            full_name: value.full_name,
+            pushed_at: value.updated_at,
+            fork: value.fork,
+            archived: value.archived,
+            is_template: value.template,
+            topics: value.topics,
+        }
+    }
+}
+
+impl GiteaSource {
+    pub fn new(token: String, options: GiteaOptions, pb: Option<ProgressBar>) -> Result<Self> {
+        Ok(Self {
+            gt: ApiClient::new(token, options.mode, &options.cache_dir, pb.clone())?,
+            options,
+            pb,
+        })
+    }
+
+    async fn get_branches(&self, repo_api_url: &str) -> Result<Vec<Branch>> {
+        self.gt
+            .get_paginated(
+                format!("{repo_api_url}/branches?limit={PAGE_SIZE}"),
+                self.pb.as_ref(),
+                |_| Ok(true),
+            )
+            .await
+    }
+
+    async fn get_commits(
+        &self,
+        repo_api_url: &str,
+        branch: Option<&str>,
+        window: Window,
+    ) -> Result<Vec<Commit>> {
+        let mut url = Url::parse(&format!("{repo_api_url}/commits"))?;
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_pair("limit", PAGE_SIZE)
+                .append_pair(
+                    "since",
+                    &window.since.to_rfc3339_opts(SecondsFormat::Secs, true),
+                )
+                .append_pair(
+                    "until",
+                    &window.until.to_rfc3339_opts(SecondsFormat::Secs, true),
+                )
+                // Diff statistics are expensive for the server and unused.
+                .append_pair("stat", "false")
+                .append_pair("verification", "false")
+                .append_pair("files", "false");
+
+            if let Some(branch) = branch {
+                query.append_pair("sha", branch);
+            }
+        }
+
+        // Older Gitea and Forgejo releases ignore `since` and `until`.
+        let commits = self
+            .gt
+            .get_paginated::<Commit, _>(url.into(), self.pb.as_ref(), |_| Ok(true))
+            .await?;
+
+        Ok(commits
+            .into_iter()
+            .filter(|i| {
+                i.commit
+                    .as_ref()
+                    .is_some_and(|c| window.contains(c.committer.date))
+            })
+            .collect())
+    }
+}
+
+impl ContributionSource for GiteaSource {
+    async fn list_repos(&self, window: Window) -> Result<Vec<Repo>> {
+        let api_url = &self.options.api_url;
+        let mut repos = vec![];
+
+        // Organization repos cannot be sorted by activity, so every page is needed.
+        for org in &self.options.orgs {
+            repos.extend(
+                self.gt
+                    .get_paginated::<GiteaRepo, _>(
+                        format!("{api_url}/orgs/{org}/repos?limit={PAGE_SIZE}"),
+                        self.pb.as_ref(),
+                        |_| Ok(true),
+                    )
+                    .await?,
+            );
+        }
+
+        for full_name in &self.options.repos {
+            repos.push(
+                self.gt
+                    .get_json(&format!("{api_url}/repos/{full_name}"))
+                    .await?,
+            );
+        }
+
+        Ok(dedup_repos(
+            repos
+                .into_iter()
+                .filter(|repo| repo.updated_at.is_some_and(|d| d >= window.since))
+                .map(Repo::from)
+                .collect(),
+        ))
+    }
+
+    async fn list_commits(&self, repo: &Repo, window: Window) -> Result<Vec<Commit>> {
+        if !self.options.all_branches {
+            return self.get_commits(&repo.url, None, window).await;
+        }
+
+        // Commits reachable from several branches are counted once.
+        let mut commits = HashMap::new();
+        for branch in self.get_branches(&repo.url).await? {
+            for i in self
+                .get_commits(&repo.url, Some(&branch.name), window)
+                .await?
+            {
+                commits.insert(i.sha.clone(), i);
+            }
+        }
+
+        Ok(commits.into_values().collect())
+    }
+
+    /// Members of the organizations visible to the token, listed once instead
+    /// of asking `/orgs/{org}/members/{user}` about each contributor.
+    async fn membership(&self) -> Result<HashMap<String, Membership>> {
+        let mut res = HashMap::new();
+
+        for org in &self.options.orgs {
+            let members = self
+                .gt
+                .get_paginated::<Author, _>(
+                    format!(
+                        "{}/orgs/{org}/members?limit={PAGE_SIZE}",
+                        self.options.api_url
+                    ),
+                    self.pb.as_ref(),
+                    |_| Ok(true),
+                )
+                .await?;
+
+            res.extend(
+                members
+                    .into_iter()
+                    .filter_map(|u| u.login)
+                    .map(|login| (login, Membership::Member)),
+            );
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::Duration;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path, query_param, query_param_is_missing},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    fn source(server: &MockServer, dir: &Path, all_branches: bool) -> GiteaSource {
+        GiteaSource::new(
+            String::new(),
+            GiteaOptions {
+                orgs: vec!["org".to_string()],
+                repos: vec!["org/c".to_string()],
+                api_url: server.uri(),
+                all_branches,
+                mode: CacheMode::Normal,
+                cache_dir: dir.to_path_buf(),
+            },
+            None,
+        )
+        .unwrap()
+    }
+
+    async fn mock(server: &MockServer, url: &str, body: serde_json::Value) {
+        Mock::given(method("GET"))
+            .and(path(url))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(server)
+            .await;
+    }
+
+    fn window() -> Window {
+        Window::new(Some(30), None, Some(Utc::now())).unwrap()
+    }
+
+    fn repo(server: &MockServer, name: &str, updated_at: DateTime<Utc>) -> serde_json::Value {
+        json!({
+            "url": format!("{}/repos/org/{name}", server.uri()),
+            "full_name": format!("org/{name}"),
+            "updated_at": updated_at,
+        })
+    }
+
+    fn commit(sha: &str, date: DateTime<Utc>) -> serde_json::Value {
+        let who = json!({ "name": "Alice", "email": "alice@example.com", "date": date });
+        json!({
+            "sha": sha,
+            "commit": { "author": who, "committer": who, "message": "fix" },
+            "author": { "login": "alice", "html_url": "https://gitea.example/alice" },
+            "committer": null,
+        })
+    }
+
+    fn shas(commits: Vec<Commit>) -> Vec<String> {
+        let mut res = commits.into_iter().map(|c| c.sha).collect::<Vec<_>>();
+        res.sort();
+        res
+    }
+
+    #[tokio::test]
+    async fn lists_active_repos_of_every_page() {
+        let server = MockServer::start().await;
+        let recent = Utc::now() - Duration::days(1);
+        let stale = Utc::now() - Duration::days(90);
+        Mock::given(method("GET"))
+            .and(path("/orgs/org/repos"))
+            .and(query_param("limit", PAGE_SIZE))
+            .and(query_param_is_missing("page"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header(
+                        "link",
+                        format!(
+                            r#"<{}/orgs/org/repos?limit={PAGE_SIZE}&page=2>; rel="next""#,
+                            server.uri()
+                        ),
+                    )
+                    .set_body_json(json!([
+                        repo(&server, "a", recent),
+                        repo(&server, "b", stale),
+                    ])),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/orgs/org/repos"))
+            .and(query_param("limit", PAGE_SIZE))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                repo(&server, "c", recent),
+                repo(&server, "d", recent),
+            ])))
+            .mount(&server)
+            .await;
+        mock(&server, "/repos/org/c", repo(&server, "c", recent)).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let repos = source(&server, dir.path(), false)
+            .list_repos(window())
+            .await
+            .unwrap();
+
+        let mut names = repos.into_iter().map(|r| r.full_name).collect::<Vec<_>>();
+        names.sort();
+        // `org/c` is also listed with `--repo`.
+        assert_eq!(names, ["org/a", "org/c", "org/d"]);
+    }
+
+    #[tokio::test]
+    async fn drops_commits_outside_the_window() {
+        let server = MockServer::start().await;
+        // As if the server ignored `since` and `until`.
+        mock(
+            &server,
+            "/repos/org/a/commits",
+            json!([
+                commit("new", Utc::now() - Duration::days(1)),
+                commit("old", Utc::now() - Duration::days(90)),
+            ]),
+        )
+        .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repo::from(
+            serde_json::from_value::<GiteaRepo>(repo(&server, "a", Utc::now())).unwrap(),
+        );
+        let commits = source(&server, dir.path(), false)
+            .list_commits(&repo, window())
+            .await
+            .unwrap();
+
+        assert_eq!(shas(commits), ["new"]);
+        let requests = server.received_requests().await.unwrap();
+        let query = requests[0].url.query().unwrap();
+        assert!(query.contains("since=") && query.contains("until="));
+    }
+
+    #[tokio::test]
+    async fn counts_commits_on_several_branches_once() {
+        let server = MockServer::start().await;
+        let date = Utc::now() - Duration::days(1);
+        mock(
+            &server,
+            "/repos/org/a/branches",
+            json!([{ "name": "main" }, { "name": "topic" }]),
+        )
+        .await;
+        for (branch, commits) in [("main", ["x", "y"]), ("topic", ["y", "z"])] {
+            Mock::given(method("GET"))
+                .and(path("/repos/org/a/commits"))
+                .and(query_param("sha", branch))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(commits.map(|sha| commit(sha, date))),
+                )
+                .mount(&server)
+                .await;
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repo::from(
+            serde_json::from_value::<GiteaRepo>(repo(&server, "a", Utc::now())).unwrap(),
+        );
+        let commits = source(&server, dir.path(), true)
+            .list_commits(&repo, window())
+            .await
+            .unwrap();
+
+        assert_eq!(shas(commits), ["x", "y", "z"]);
+    }
+
+    #[tokio::test]
+    async fn lists_members() {
+        let server = MockServer::start().await;
+        mock(
+            &server,
+            "/orgs/org/members",
+            json!([
+                { "login": "alice", "html_url": "https://gitea.example/alice" },
+                { "login": "bob", "html_url": "https://gitea.example/bob" },
+            ]),
+        )
+        .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let membership = source(&server, dir.path(), false)
+            .membership()
+            .await
+            .unwrap();
+
+        assert_eq!(membership.len(), 2);
+        assert_eq!(membership.get("bob"), Some(&Membership::Member));
+    }
+}
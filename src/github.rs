@@ -0,0 +1,725 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use chrono::SecondsFormat;
+use clap::ValueEnum;
+use eyre::{bail, Result};
+use indicatif::ProgressBar;
+use reqwest::{StatusCode, Url};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::{
+    cache::{CacheMode, CommitCache},
+    client::ApiClient,
+    graphql,
+    issues::{self, IssueActivity},
+    pulls::{self, PullActivity},
+    source::{dedup_repos, list_commits_concurrently, ContributionSource},
+    teams::{self, Team},
+    Author, Branch, Commit, FileChange, Membership, Repo, Window,
+};
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Api {
+    /// One REST request per page of commits of each repo
+    #[default]
+    Rest,
+    /// Batched GraphQL history queries, default branches only
+    Graphql,
+}
+
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+
+/// What a [`GitHubSource`] fetches.
+#[derive(Debug, Clone)]
+pub struct GitHubOptions {
+    pub orgs: Vec<String>,
+    /// Repositories (`owner/name`) counted besides those of `orgs`
+    pub repos: Vec<String>,
+    /// Base URL of the REST API, e.g. `https://github.example.com/api/v3` for
+    /// GitHub Enterprise Server
+    pub api_url: String,
+    pub api: Api,
+    /// Count commits on every branch instead of only the default branch
+    pub all_branches: bool,
+    /// Also list outside collaborators, requires an org owner token
+    pub outside_collaborators: bool,
+    pub mode: CacheMode,
+    /// Where responses are cached, usually [`crate::cache::default_dir`]
+    pub cache_dir: PathBuf,
+}
+
+#[derive(Deserialize, Debug)]
+struct CommitDetail {
+    #[serde(default)]
+    files: Vec<FileChange>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchUsers {
+    items: Vec<Author>,
+}
+
+/// Contributions to a GitHub organization.
+pub struct GitHubSource {
+    gh: ApiClient,
+    cache: CommitCache,
+    options: GitHubOptions,
+    pb: Option<ProgressBar>,
+}
+
+impl GitHubSource {
+    pub fn new(token: String, options: GitHubOptions, pb: Option<ProgressBar>) -> Result<Self> {
+        Ok(Self {
+            gh: ApiClient::new(token, options.mode, &options.cache_dir, pb.clone())?,
+            cache: CommitCache::new(&options.cache_dir, &options.api_url)?,
+            options,
+            pb,
+        })
+    }
+
+    async fn get_repos(&self, org: &str, window: Window) -> Result<Vec<Repo>> {
+        // The list is sorted by push time, so the first repo pushed before the
+        // window means every following one is too.
+        self.gh
+            .get_paginated(
+                format!(
+                    "{}/orgs/{org}/repos?per_page=100&sort=pushed",
+                    self.options.api_url
+                ),
+                self.pb.as_ref(),
+                |repo: &Repo| Ok(repo.pushed_at.is_some_and(|d| d >= window.since)),
+            )
+            .await
+    }
+
+    async fn get_repo(&self, full_name: &str) -> Result<Repo> {
+        self.gh
+            .get_json(&format!("{}/repos/{full_name}", self.options.api_url))
+            .await
+    }
+
+    async fn get_branches(&self, repo_api_url: &str) -> Result<Vec<Branch>> {
+        self.gh
+            .get_paginated(
+                format!("{}/branches?per_page=100", repo_api_url),
+                self.pb.as_ref(),
+                |_| Ok(true),
+            )
+            .await
+    }
+
+    async fn get_commits(
+        &self,
+        repo_api_url: &str,
+        branch: Option<&str>,
+        window: Window,
+    ) -> Result<Vec<Commit>> {
+        let mut url = Url::parse(&format!("{}/commits", repo_api_url))?;
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_pair("per_page", "100")
+                .append_pair(
+                    "since",
+                    &window.since.to_rfc3339_opts(SecondsFormat::Secs, true),
+                )
+                .append_pair(
+                    "until",
+                    &window.until.to_rfc3339_opts(SecondsFormat::Secs, true),
+                );
+
+            if let Some(branch) = branch {
+                query.append_pair("sha", branch);
+            }
+        }
+
+        self.gh
+            .get_paginated(url.into(), self.pb.as_ref(), |_| Ok(true))
+            .await
+    }
+
+    async fn get_users(&self, url: String) -> Result<Vec<String>> {
+        Ok(self
+            .gh
+            .get_paginated::<Author, _>(url, self.pb.as_ref(), |_| Ok(true))
+            .await?
+            .into_iter()
+            .filter_map(|u| u.login)
+            .collect())
+    }
+
+    /// Same as [`ContributionSource::list_commits`] on the default branch of every
+    /// repo, with GraphQL queries covering many repos at once. Repos the queries
+    /// fail for are fetched again with REST, `thread` at a time.
+    async fn get_commits_by_graphql(
+        &self,
+        repos: &[Repo],
+        window: Window,
+        thread: usize,
+    ) -> Vec<Result<(String, Vec<Commit>)>> {
+        let mut cached = HashMap::new();
+        let mut missing = vec![];
+
+        for repo in repos {
+            let repo_cache = match self.options.mode {
+                CacheMode::Refresh => Default::default(),
+                _ => self.cache.load(&repo.full_name),
+            };
+
+            if let Some(window) = repo_cache.missing(None, window, repo.pushed_at) {
+                missing.push((repo.full_name.clone(), window));
+            }
+
+            cached.insert(repo.full_name.clone(), repo_cache);
+        }
+
+        let mut fetched = graphql::get_commits(
+            &self.gh,
+            &graphql::graphql_url(&self.options.api_url),
+            missing.clone(),
+            self.pb.as_ref(),
+        )
+        .await;
+
+        let mut failed = vec![];
+
+        for (repo, missing) in missing {
+            let commits = match fetched.remove(&repo) {
+                Some(Ok(commits)) => commits,
+                Some(Err(e)) => {
+                    warn!("Falling back to REST for {repo}: {e}");
+                    cached.remove(&repo);
+                    failed.push(repo);
+                    continue;
+                }
+                None => vec![],
+            };
+
+            let Some(repo_cache) = cached.get_mut(&repo) else {
+                continue;
+            };
+
+            repo_cache.insert(None, missing, commits);
+            if let Err(e) = self.cache.store(&repo, repo_cache) {
+                warn!("Failed to cache commits of {}: {e}", repo);
+            }
+        }
+
+        let mut res = cached
+            .into_iter()
+            .map(|(repo, repo_cache)| Ok((repo, repo_cache.into_commits(window, &[None]))))
+            .collect::<Vec<_>>();
+
+        let failed = repos
+            .iter()
+            .filter(|r| failed.contains(&r.full_name))
+            .cloned()
+            .collect::<Vec<_>>();
+        res.extend(list_commits_concurrently(self, &failed, window, thread).await);
+
+        res
+    }
+}
+
+impl ContributionSource for GitHubSource {
+    async fn list_repos(&self, window: Window) -> Result<Vec<Repo>> {
+        let mut res = vec![];
+
+        if self.options.mode != CacheMode::Offline {
+            for org in &self.options.orgs {
+                res.extend(self.get_repos(org, window).await?);
+            }
+
+            for full_name in &self.options.repos {
+                let repo = self.get_repo(full_name).await?;
+                if repo.pushed_at.is_some_and(|d| d >= window.since) {
+                    res.push(repo);
+                }
+            }
+
+            return Ok(dedup_repos(res));
+        }
+
+        // Only names are known offline.
+        let mut names = vec![];
+        for org in &self.options.orgs {
+            names.extend(self.cache.repos(org)?);
+        }
+        names.extend(self.options.repos.iter().cloned());
+
+        Ok(dedup_repos(
+            names
+                .into_iter()
+                .map(|full_name| Repo {
+                    url: format!("{}/repos/{full_name}", self.options.api_url),
+                    full_name,
+                    pushed_at: None,
+                    fork: false,
+                    archived: false,
+                    is_template: false,
+                    topics: vec![],
+                })
+                .collect(),
+        ))
+    }
+
+    async fn list_commits(&self, repo: &Repo, window: Window) -> Result<Vec<Commit>> {
+        let url = &repo.url;
+        let mut cached = match self.options.mode {
+            CacheMode::Refresh => Default::default(),
+            _ => self.cache.load(&repo.full_name),
+        };
+
+        if self.options.mode == CacheMode::Offline {
+            let branches = if self.options.all_branches {
+                cached.branches()
+            } else {
+                vec![None]
+            };
+
+            return Ok(cached.into_commits(window, &branches));
+        }
+
+        let branches = if self.options.all_branches {
+            self.get_branches(url)
+                .await?
+                .into_iter()
+                .map(|b| Some(b.name))
+                .collect()
+        } else {
+            vec![None]
+        };
+
+        let mut fetched = false;
+
+        for branch in &branches {
+            let branch = branch.as_deref();
+            let Some(missing) = cached.missing(branch, window, repo.pushed_at) else {
+                continue;
+            };
+
+            match self.get_commits(url, branch, missing).await {
+                Ok(commits) => {
+                    cached.insert(branch, missing, commits);
+                    fetched = true;
+                }
+                Err(e) => match e.downcast_ref::<reqwest::Error>().and_then(|e| e.status()) {
+                    Some(StatusCode::CONFLICT) => {
+                        bail!("Git Repository is empty: {}", e)
+                    }
+                    _ => bail!("Failed to get commits {}: {e}", url),
+                },
+            }
+        }
+
+        if fetched {
+            if let Err(e) = self.cache.store(&repo.full_name, &cached) {
+                warn!("Failed to cache commits of {}: {e}", repo.full_name);
+            }
+        }
+
+        Ok(cached.into_commits(window, &branches))
+    }
+
+    /// Resolve the membership of every known org user by listing members once,
+    /// instead of asking about each contributor. Members of any of the orgs
+    /// count as members.
+    async fn membership(&self) -> Result<HashMap<String, Membership>> {
+        let api_url = &self.options.api_url;
+        let mut res = HashMap::new();
+
+        let members = async {
+            let mut members = vec![];
+
+            for org in &self.options.orgs {
+                if self.options.outside_collaborators {
+                    let users = self
+                        .get_users(format!(
+                            "{api_url}/orgs/{org}/outside_collaborators?per_page=100"
+                        ))
+                        .await?;
+
+                    for login in users {
+                        res.insert(login, Membership::OutsideCollaborator);
+                    }
+                }
+
+                members.extend(
+                    self.get_users(format!("{api_url}/orgs/{org}/members?per_page=100"))
+                        .await?,
+                );
+            }
+
+            Ok(members)
+        }
+        .await;
+
+        let members = match members {
+            Ok(members) => members,
+            Err(e) if self.options.mode == CacheMode::Offline => {
+                warn!("Membership is unknown offline: {e}");
+                return Ok(HashMap::new());
+            }
+            Err(e) => return Err(e),
+        };
+
+        for login in members {
+            res.insert(login, Membership::Member);
+        }
+
+        Ok(res)
+    }
+
+    /// Teams of every org, with the same slug possibly in several of them.
+    async fn teams(&self, with_repos: bool) -> Result<Vec<Team>> {
+        let mut res = vec![];
+
+        for org in &self.options.orgs {
+            res.extend(
+                teams::get_teams(
+                    &self.gh,
+                    &self.options.api_url,
+                    org,
+                    with_repos,
+                    self.pb.as_ref(),
+                )
+                .await?,
+            );
+        }
+
+        Ok(res)
+    }
+
+    async fn list_pulls(&self, repo: &Repo, window: Window) -> Result<Vec<PullActivity>> {
+        pulls::get_pulls(&self.gh, &repo.url, window, self.pb.as_ref()).await
+    }
+
+    async fn list_issues(&self, repo: &Repo, window: Window) -> Result<IssueActivity> {
+        issues::get_issues(&self.gh, &repo.url, window, self.pb.as_ref()).await
+    }
+
+    /// GitHub lists at most 300 files per commit, the rest are not counted.
+    async fn commit_files(&self, repo: &Repo, sha: &str) -> Result<Vec<FileChange>> {
+        Ok(self
+            .gh
+            .get_json::<CommitDetail>(&format!("{}/commits/{sha}", repo.url))
+            .await?
+            .files)
+    }
+
+    async fn find_user(&self, email: &str) -> Result<Option<Author>> {
+        let api_url = &self.options.api_url;
+
+        // `{id}+{login}@users.noreply.github.com`, as used by the web interface.
+        if let Some(local) = email.strip_suffix("@users.noreply.github.com") {
+            let login = local.split_once('+').map_or(local, |(_, login)| login);
+            return Ok(Some(
+                self.gh
+                    .get_json(&format!("{api_url}/users/{login}"))
+                    .await?,
+            ));
+        }
+
+        let mut url = Url::parse(&format!("{api_url}/search/users"))?;
+        url.query_pairs_mut()
+            .append_pair("q", &format!("{email} in:email"));
+
+        let found = self.gh.get_json::<SearchUsers>(url.as_str()).await?;
+        // Anything but a single match is a guess.
+        Ok(match <[Author; 1]>::try_from(found.items) {
+            Ok([user]) => Some(user),
+            Err(_) => None,
+        })
+    }
+
+    async fn list_all_commits(
+        &self,
+        repos: &[Repo],
+        window: Window,
+        thread: usize,
+    ) -> Vec<Result<(String, Vec<Commit>)>> {
+        if self.options.api != Api::Graphql || self.options.mode == CacheMode::Offline {
+            return list_commits_concurrently(self, repos, window, thread).await;
+        }
+
+        self.get_commits_by_graphql(repos, window, thread).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::{DateTime, Duration, Utc};
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path, query_param, query_param_is_missing},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    fn options(api_url: String, dir: &Path) -> GitHubOptions {
+        GitHubOptions {
+            orgs: vec!["org".to_string()],
+            repos: vec![],
+            api_url,
+            api: Api::Rest,
+            all_branches: false,
+            outside_collaborators: false,
+            mode: CacheMode::Normal,
+            cache_dir: dir.to_path_buf(),
+        }
+    }
+
+    fn source(options: GitHubOptions) -> GitHubSource {
+        GitHubSource::new(String::new(), options, None).unwrap()
+    }
+
+    async fn mock(server: &MockServer, url: &str, body: serde_json::Value) {
+        Mock::given(method("GET"))
+            .and(path(url))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(server)
+            .await;
+    }
+
+    fn window() -> Window {
+        Window::new(Some(30), None, Some(Utc::now())).unwrap()
+    }
+
+    fn repo(api_url: &str, name: &str, pushed_at: DateTime<Utc>) -> Repo {
+        Repo {
+            url: format!("{api_url}/repos/org/{name}"),
+            full_name: format!("org/{name}"),
+            pushed_at: Some(pushed_at),
+            fork: false,
+            archived: false,
+            is_template: false,
+            topics: vec![],
+        }
+    }
+
+    fn repo_json(server: &MockServer, name: &str, pushed_at: DateTime<Utc>) -> serde_json::Value {
+        json!({
+            "url": format!("{}/repos/org/{name}", server.uri()),
+            "full_name": format!("org/{name}"),
+            "pushed_at": pushed_at,
+        })
+    }
+
+    fn commit(sha: &str, date: DateTime<Utc>) -> serde_json::Value {
+        let who = json!({ "name": "Alice", "email": "alice@example.com", "date": date });
+        json!({
+            "sha": sha,
+            "commit": { "author": who, "committer": who, "message": "fix" },
+            "author": { "login": "alice", "html_url": "https://github.com/alice", "type": "User" },
+            "committer": null,
+        })
+    }
+
+    fn shas(commits: &[Commit]) -> Vec<&str> {
+        let mut res = commits.iter().map(|c| c.sha.as_str()).collect::<Vec<_>>();
+        res.sort();
+        res
+    }
+
+    #[tokio::test]
+    async fn lists_repos_until_one_was_pushed_before_the_window() {
+        let server = MockServer::start().await;
+        let recent = Utc::now() - Duration::days(1);
+        let stale = Utc::now() - Duration::days(90);
+        Mock::given(method("GET"))
+            .and(path("/orgs/org/repos"))
+            .and(query_param("sort", "pushed"))
+            .and(query_param_is_missing("page"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header(
+                        "link",
+                        format!(
+                            r#"<{}/orgs/org/repos?per_page=100&sort=pushed&page=2>; rel="next""#,
+                            server.uri()
+                        ),
+                    )
+                    .set_body_json(json!([repo_json(&server, "a", recent)])),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/orgs/org/repos"))
+            .and(query_param("page", "2"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header(
+                        "link",
+                        format!(
+                            r#"<{}/orgs/org/repos?per_page=100&sort=pushed&page=3>; rel="next""#,
+                            server.uri()
+                        ),
+                    )
+                    .set_body_json(json!([
+                        repo_json(&server, "b", recent),
+                        repo_json(&server, "c", stale),
+                        repo_json(&server, "d", recent),
+                    ])),
+            )
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let repos = source(options(server.uri(), dir.path()))
+            .list_repos(window())
+            .await
+            .unwrap();
+
+        let names = repos.into_iter().map(|r| r.full_name).collect::<Vec<_>>();
+        assert_eq!(names, ["org/a", "org/b"]);
+        // The third page is never requested.
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn caches_the_commits_of_the_window() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/org/a/commits"))
+            .and(query_param("per_page", "100"))
+            .and(query_param_is_missing("sha"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!([commit("abc", Utc::now() - Duration::days(2))])),
+            )
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let window = window();
+        let repo = repo(&server.uri(), "a", Utc::now() - Duration::days(1));
+
+        let commits = source(options(server.uri(), dir.path()))
+            .list_commits(&repo, window)
+            .await
+            .unwrap();
+        assert_eq!(shas(&commits), ["abc"]);
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 1);
+        let query = requests[0].url.query_pairs().collect::<HashMap<_, _>>();
+        assert_eq!(
+            query["since"],
+            window.since.to_rfc3339_opts(SecondsFormat::Secs, true)
+        );
+        assert_eq!(
+            query["until"],
+            window.until.to_rfc3339_opts(SecondsFormat::Secs, true)
+        );
+
+        // Nothing was pushed since, so the cache answers alone.
+        let commits = source(options(server.uri(), dir.path()))
+            .list_commits(&repo, window)
+            .await
+            .unwrap();
+        assert_eq!(shas(&commits), ["abc"]);
+
+        let mut offline = options(server.uri(), dir.path());
+        offline.mode = CacheMode::Offline;
+        let commits = source(offline).list_commits(&repo, window).await.unwrap();
+        assert_eq!(shas(&commits), ["abc"]);
+
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn lists_commits_of_every_branch() {
+        let server = MockServer::start().await;
+        let date = Utc::now() - Duration::days(2);
+        mock(
+            &server,
+            "/repos/org/a/branches",
+            json!([{ "name": "main" }, { "name": "topic" }]),
+        )
+        .await;
+        for (branch, commits) in [("main", ["x", "y"]), ("topic", ["y", "z"])] {
+            Mock::given(method("GET"))
+                .and(path("/repos/org/a/commits"))
+                .and(query_param("sha", branch))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(commits.map(|sha| commit(sha, date))),
+                )
+                .mount(&server)
+                .await;
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut options = options(server.uri(), dir.path());
+        options.all_branches = true;
+        let commits = source(options)
+            .list_commits(&repo(&server.uri(), "a", Utc::now()), window())
+            .await
+            .unwrap();
+
+        assert_eq!(shas(&commits), ["x", "y", "z"]);
+    }
+
+    #[tokio::test]
+    async fn lists_members_and_outside_collaborators() {
+        let server = MockServer::start().await;
+        mock(
+            &server,
+            "/orgs/org/members",
+            json!([{ "login": "alice", "html_url": "https://github.com/alice" }]),
+        )
+        .await;
+        mock(
+            &server,
+            "/orgs/org/outside_collaborators",
+            json!([{ "login": "bob", "html_url": "https://github.com/bob" }]),
+        )
+        .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut options = options(server.uri(), dir.path());
+        options.outside_collaborators = true;
+        let membership = source(options).membership().await.unwrap();
+
+        assert_eq!(membership.len(), 2);
+        assert_eq!(membership.get("alice"), Some(&Membership::Member));
+        assert_eq!(
+            membership.get("bob"),
+            Some(&Membership::OutsideCollaborator)
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_graphql_queries_next_to_the_rest_api() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "r0": { "defaultBranchRef": { "target": { "history": {
+                    "pageInfo": { "hasNextPage": false, "endCursor": null },
+                    "nodes": [{
+                        "oid": "abc",
+                        "author": { "name": "A", "email": "a@x", "date": Utc::now() - Duration::days(2), "user": null },
+                        "committer": { "name": "A", "email": "a@x", "date": Utc::now() - Duration::days(2), "user": null }
+                    }]
+                } } } } }
+            })))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let api_url = format!("{}/api/v3", server.uri());
+        let mut options = options(api_url.clone(), dir.path());
+        options.api = Api::Graphql;
+        let repos = [repo(&api_url, "a", Utc::now())];
+        let mut res = source(options).list_all_commits(&repos, window(), 1).await;
+
+        let (name, commits) = res.pop().unwrap().unwrap();
+        assert_eq!(name, "org/a");
+        assert_eq!(shas(&commits), ["abc"]);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+}
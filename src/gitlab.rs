@@ -0,0 +1,438 @@
+use std::{collections::HashMap, path::PathBuf, sync::Mutex};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use eyre::Result;
+use indicatif::ProgressBar;
+use reqwest::Url;
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::{
+    cache::CacheMode,
+    client::ApiClient,
+    source::{dedup_repos, ContributionSource},
+    Author, Commit, Membership, Repo, RepoAuthor, RepoCommit, Window,
+};
+
+pub const GITLAB_API_URL: &str = "https://gitlab.com/api/v4";
+
+/// What a [`GitLabSource`] fetches.
+#[derive(Debug, Clone)]
+pub struct GitLabOptions {
+    /// Full paths of the groups, e.g. `gitlab-org` or `gitlab-org/charts`
+    pub groups: Vec<String>,
+    /// Full paths of projects counted besides those of `groups`
+    pub repos: Vec<String>,
+    /// Base URL of the REST API, e.g. `https://gitlab.com/api/v4`
+    pub api_url: String,
+    /// Count commits on every branch instead of only the default branch
+    pub all_branches: bool,
+    pub mode: CacheMode,
+    /// Where responses are cached, usually [`crate::cache::default_dir`]
+    pub cache_dir: PathBuf,
+}
+
+/// Contributions to a GitLab group and its subgroups.
+///
+/// GitLab commits only carry names and emails, they are attributed to the
+/// user whose public email matches.
+pub struct GitLabSource {
+    gl: ApiClient,
+    options: GitLabOptions,
+    /// Users looked up by email, `None` for emails without a matching user
+    users: Mutex<HashMap<String, Option<Author>>>,
+    pb: Option<ProgressBar>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Project {
+    id: u64,
+    path_with_namespace: String,
+    last_activity_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    forked_from_project: Option<serde_json::Value>,
+    #[serde(default)]
+    archived: bool,
+    #[serde(default)]
+    topics: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GitLabCommit {
+    id: String,
+    author_name: String,
+    author_email: String,
+    authored_date: DateTime<Utc>,
+    committer_name: String,
+    committer_email: String,
+    committed_date: DateTime<Utc>,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct User {
+    username: String,
+    web_url: String,
+}
+
+impl From<User> for Author {
+    fn from(value: User) -> Self {
+        Author {
+            login: Some(value.username),
+            html_url: Some(value.web_url),
+            user_type: None,
+        }
+    }
+}
+
+impl GitLabSource {
+    pub fn new(token: String, options: GitLabOptions, pb: Option<ProgressBar>) -> Result<Self> {
+        Ok(Self {
+            gl: ApiClient::new(token, options.mode, &options.cache_dir, pb.clone())?,
+            options,
+            users: Mutex::new(HashMap::new()),
+            pb,
+        })
+    }
+
+    async fn get_projects(&self, group: &str, window: Window) -> Result<Vec<Project>> {
+        // The list is sorted by activity, so the first project inactive since
+        // the window means every following one is too.
+        self.gl
+            .get_paginated(
+                format!(
+                    "{}/groups/{}/projects?include_subgroups=true&with_shared=false&order_by=last_activity_at&sort=desc&per_page=100",
+                    self.options.api_url,
+                    path_id(group)
+                ),
+                self.pb.as_ref(),
+                |project: &Project| {
+                    Ok(project
+                        .last_activity_at
+                        .is_some_and(|d| d >= window.since))
+                },
+            )
+            .await
+    }
+
+    /// [`Self::user_by_email`] for commits, where a failed lookup is logged and
+    /// leaves the user unknown instead of failing the whole project.
+    async fn commit_user(&self, email: &str) -> Option<Author> {
+        match self.user_by_email(email).await {
+            Ok(user) => user,
+            Err(e) => {
+                warn!("Failed to find the user of {email}: {e}");
+                None
+            }
+        }
+    }
+
+    /// The user whose public email is `email`, looked up once per email.
+    async fn user_by_email(&self, email: &str) -> Result<Option<Author>> {
+        if let Some(user) = self.users.lock().unwrap().get(email) {
+            return Ok(user.clone());
+        }
+
+        let mut url = Url::parse(&format!("{}/users", self.options.api_url))?;
+        url.query_pairs_mut().append_pair("search", email);
+
+        let found = self.gl.get_json::<Vec<User>>(url.as_str()).await?;
+        // Anything but a single match is a guess.
+        let user = match <[User; 1]>::try_from(found) {
+            Ok([user]) => Some(Author::from(user)),
+            Err(_) => None,
+        };
+
+        self.users
+            .lock()
+            .unwrap()
+            .insert(email.to_string(), user.clone());
+
+        Ok(user)
+    }
+}
+
+impl ContributionSource for GitLabSource {
+    async fn list_repos(&self, window: Window) -> Result<Vec<Repo>> {
+        let api_url = &self.options.api_url;
+        let mut projects = vec![];
+
+        for group in &self.options.groups {
+            projects.extend(self.get_projects(group, window).await?);
+        }
+
+        for path in &self.options.repos {
+            let project = self
+                .gl
+                .get_json::<Project>(&format!("{api_url}/projects/{}", path_id(path)))
+                .await?;
+            if project.last_activity_at.is_some_and(|d| d >= window.since) {
+                projects.push(project);
+            }
+        }
+
+        Ok(dedup_repos(
+            projects
+                .into_iter()
+                .map(|p| Repo {
+                    url: format!("{api_url}/projects/{}", p.id),
+                    full_name: p.path_with_namespace,
+                    pushed_at: p.last_activity_at,
+                    fork: p.forked_from_project.is_some(),
+                    archived: p.archived,
+                    is_template: false,
+                    topics: p.topics,
+                })
+                .collect(),
+        ))
+    }
+
+    async fn list_commits(&self, repo: &Repo, window: Window) -> Result<Vec<Commit>> {
+        let mut url = Url::parse(&format!("{}/repository/commits", repo.url))?;
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_pair("per_page", "100")
+                .append_pair(
+                    "since",
+                    &window.since.to_rfc3339_opts(SecondsFormat::Secs, true),
+                )
+                .append_pair(
+                    "until",
+                    &window.until.to_rfc3339_opts(SecondsFormat::Secs, true),
+                );
+
+            if self.options.all_branches {
+                query.append_pair("all", "true");
+            }
+        }
+
+        let commits = self
+            .gl
+            .get_paginated::<GitLabCommit, _>(url.into(), self.pb.as_ref(), |_| Ok(true))
+            .await?;
+
+        let mut res = vec![];
+        for i in commits {
+            res.push(Commit {
+                author: self.commit_user(&i.author_email).await,
+                committer: self.commit_user(&i.committer_email).await,
+                commit: Some(RepoCommit {
+                    author: RepoAuthor {
+                        name: i.author_name,
+                        email: i.author_email,
+                        date: i.authored_date,
+                    },
+                    committer: RepoAuthor {
+                        name: i.committer_name,
+                        email: i.committer_email,
+                        date: i.committed_date,
+                    },
+                    message: i.message,
+                }),
+                sha: i.id,
+            });
+        }
+
+        Ok(res)
+    }
+
+    async fn find_user(&self, email: &str) -> Result<Option<Author>> {
+        self.user_by_email(email).await
+    }
+
+    /// Direct and inherited members of the groups.
+    async fn membership(&self) -> Result<HashMap<String, Membership>> {
+        let mut res = HashMap::new();
+
+        for group in &self.options.groups {
+            let members = self
+                .gl
+                .get_paginated::<User, _>(
+                    format!(
+                        "{}/groups/{}/members/all?per_page=100",
+                        self.options.api_url,
+                        path_id(group)
+                    ),
+                    self.pb.as_ref(),
+                    |_| Ok(true),
+                )
+                .await?;
+
+            res.extend(
+                members
+                    .into_iter()
+                    .map(|u| (u.username, Membership::Member)),
+            );
+        }
+
+        Ok(res)
+    }
+}
+
+/// A group or project path as a single URL path segment.
+fn path_id(path: &str) -> String {
+    path.replace('/', "%2F")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::Duration;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    fn source(server: &MockServer, dir: &Path) -> GitLabSource {
+        GitLabSource::new(
+            String::new(),
+            GitLabOptions {
+                groups: vec!["grp/sub".to_string()],
+                repos: vec![],
+                api_url: server.uri(),
+                all_branches: false,
+                mode: CacheMode::Normal,
+                cache_dir: dir.to_path_buf(),
+            },
+            None,
+        )
+        .unwrap()
+    }
+
+    async fn mock(server: &MockServer, url: &str, body: serde_json::Value) {
+        Mock::given(method("GET"))
+            .and(path(url))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(server)
+            .await;
+    }
+
+    fn window() -> Window {
+        Window::new(Some(30), None, Some(Utc::now())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn lists_projects_of_subgroups() {
+        let server = MockServer::start().await;
+        let recent = Utc::now() - Duration::days(1);
+        let stale = Utc::now() - Duration::days(90);
+        Mock::given(method("GET"))
+            .and(path("/groups/grp%2Fsub/projects"))
+            .and(query_param("include_subgroups", "true"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                { "id": 1, "path_with_namespace": "grp/sub/app", "last_activity_at": recent, "topics": ["rust"] },
+                { "id": 2, "path_with_namespace": "grp/sub/deep/fork", "last_activity_at": recent, "forked_from_project": { "id": 9 } },
+                { "id": 3, "path_with_namespace": "grp/sub/old", "last_activity_at": stale },
+            ])))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let repos = source(&server, dir.path())
+            .list_repos(window())
+            .await
+            .unwrap();
+
+        let names = repos
+            .iter()
+            .map(|r| r.full_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["grp/sub/app", "grp/sub/deep/fork"]);
+        assert_eq!(repos[0].url, format!("{}/projects/1", server.uri()));
+        assert_eq!(repos[0].topics, ["rust"]);
+        assert!(!repos[0].fork && repos[1].fork);
+    }
+
+    #[tokio::test]
+    async fn maps_commits_to_users_by_email() {
+        let server = MockServer::start().await;
+        let date = Utc::now() - Duration::days(1);
+        mock(
+            &server,
+            "/projects/1/repository/commits",
+            json!([{
+                "id": "abc",
+                "author_name": "Alice",
+                "author_email": "alice@example.com",
+                "authored_date": date,
+                "committer_name": "Bob",
+                "committer_email": "bob@example.com",
+                "committed_date": date,
+                "message": "Fix\n\nCo-authored-by: Carol <carol@example.com>",
+            }]),
+        )
+        .await;
+        Mock::given(method("GET"))
+            .and(path("/users"))
+            .and(query_param("search", "alice@example.com"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                { "username": "alice", "web_url": "https://gitlab.example/alice" },
+            ])))
+            .mount(&server)
+            .await;
+        // Ambiguous matches are not guessed.
+        Mock::given(method("GET"))
+            .and(path("/users"))
+            .and(query_param("search", "bob@example.com"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                { "username": "bob", "web_url": "https://gitlab.example/bob" },
+                { "username": "bobby", "web_url": "https://gitlab.example/bobby" },
+            ])))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let source = source(&server, dir.path());
+        let repo = Repo {
+            url: format!("{}/projects/1", server.uri()),
+            full_name: "grp/sub/app".to_string(),
+            pushed_at: Some(date),
+            fork: false,
+            archived: false,
+            is_template: false,
+            topics: vec![],
+        };
+        let commits = source.list_commits(&repo, window()).await.unwrap();
+
+        assert_eq!(commits.len(), 1);
+        let commit = &commits[0];
+        assert_eq!(commit.sha, "abc");
+        assert_eq!(
+            commit.author.as_ref().and_then(|a| a.login.as_deref()),
+            Some("alice")
+        );
+        assert!(commit.committer.is_none());
+
+        let info = commit.commit.as_ref().unwrap();
+        assert_eq!(info.author.email, "alice@example.com");
+        assert_eq!(info.committer.name, "Bob");
+        assert_eq!(info.committer.date, date);
+        assert!(info.message.contains("Co-authored-by"));
+    }
+
+    #[tokio::test]
+    async fn lists_inherited_members() {
+        let server = MockServer::start().await;
+        mock(
+            &server,
+            "/groups/grp%2Fsub/members/all",
+            json!([
+                { "username": "alice", "web_url": "https://gitlab.example/alice" },
+                { "username": "inherited", "web_url": "https://gitlab.example/inherited" },
+            ]),
+        )
+        .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let membership = source(&server, dir.path()).membership().await.unwrap();
+
+        assert_eq!(membership.len(), 2);
+        assert_eq!(membership.get("inherited"), Some(&Membership::Member));
+    }
+}
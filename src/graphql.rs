@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use eyre::{eyre, Result};
+use indicatif::ProgressBar;
+use serde::Deserialize;
+
+use crate::{client::ApiClient, update_pb, Author, Commit, RepoAuthor, RepoCommit, Window};
+
+/// Repositories queried at once, each one is an aliased field of the same query.
+const BATCH_SIZE: usize = 20;
+
+#[derive(Deserialize, Debug)]
+struct Repository {
+    #[serde(rename = "defaultBranchRef")]
+    default_branch_ref: Option<Ref>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Ref {
+    target: Option<Target>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Target {
+    history: History,
+}
+
+#[derive(Deserialize, Debug)]
+struct History {
+    #[serde(rename = "pageInfo")]
+    page_info: PageInfo,
+    nodes: Vec<HistoryCommit>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PageInfo {
+    #[serde(rename = "hasNextPage")]
+    has_next_page: bool,
+    #[serde(rename = "endCursor")]
+    end_cursor: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct HistoryCommit {
+    oid: String,
+    #[serde(default)]
+    message: String,
+    author: Option<GitActor>,
+    committer: Option<GitActor>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GitActor {
+    name: Option<String>,
+    email: Option<String>,
+    date: Option<DateTime<Utc>>,
+    user: Option<User>,
+}
+
+#[derive(Deserialize, Debug)]
+struct User {
+    login: String,
+    url: String,
+}
+
+impl From<HistoryCommit> for Commit {
+    fn from(value: HistoryCommit) -> Self {
+        let identity = |actor: &Option<GitActor>| {
+            let actor = actor.as_ref()?;
+            Some(RepoAuthor {
+                name: actor.name.clone().unwrap_or_default(),
+                email: actor.email.clone().unwrap_or_default(),
+                date: actor.date?,
+            })
+        };
+        let commit = match (identity(&value.author), identity(&value.committer)) {
+            (Some(author), Some(committer)) => Some(RepoCommit {
+                author,
+                committer,
+                message: value.message,
+            }),
+            _ => None,
+        };
+
+        // `user` is only ever a `User`, GitHub Apps are recognized by their
+        // noreply email like REST does.
+        let user = |actor: Option<GitActor>| {
+            let actor = actor?;
+            match actor.user {
+                Some(u) => Some(Author {
+                    login: Some(u.login),
+                    html_url: Some(u.url),
+                    user_type: None,
+                }),
+                None => app(actor.email.as_deref()?),
+            }
+        };
+
+        Commit {
+            sha: value.oid,
+            commit,
+            author: user(value.author),
+            committer: user(value.committer),
+        }
+    }
+}
+
+/// The GitHub App behind a `<id>+<name>[bot]@users.noreply.<host>` email.
+fn app(email: &str) -> Option<Author> {
+    let (local, domain) = email.rsplit_once('@')?;
+    let host = domain.strip_prefix("users.noreply.")?;
+    let login = local.split_once('+').map_or(local, |(_, login)| login);
+    let name = login.strip_suffix("[bot]")?;
+
+    Some(Author {
+        login: Some(login.to_string()),
+        html_url: Some(format!("https://{host}/apps/{name}")),
+        user_type: Some("Bot".to_string()),
+    })
+}
+
+/// A repository whose default branch history is still being fetched.
+struct Pending {
+    full_name: String,
+    window: Window,
+    cursor: Option<String>,
+}
+
+/// The GraphQL endpoint next to the REST API at `api_url`, GitHub Enterprise
+/// Server serves them at `/api/graphql` and `/api/v3`.
+pub fn graphql_url(api_url: &str) -> String {
+    let api_url = api_url.trim_end_matches('/');
+    match api_url.strip_suffix("/v3") {
+        Some(base) => format!("{base}/graphql"),
+        None => format!("{api_url}/graphql"),
+    }
+}
+
+/// Fetch commits on the default branch of many repositories with batched GraphQL
+/// `history` queries, `repos` pairs each `owner/name` with the interval to fetch.
+///
+/// A repository the query failed for, alone or with its whole batch, maps to
+/// the error and the others are kept.
+pub async fn get_commits(
+    gh: &ApiClient,
+    url: &str,
+    repos: Vec<(String, Window)>,
+    pb: Option<&ProgressBar>,
+) -> HashMap<String, Result<Vec<Commit>>> {
+    let mut res = HashMap::new();
+    let mut pending = repos
+        .into_iter()
+        .map(|(full_name, window)| Pending {
+            full_name,
+            window,
+            cursor: None,
+        })
+        .collect::<Vec<_>>();
+    let mut page = 1;
+
+    while !pending.is_empty() {
+        let mut next = vec![];
+
+        for batch in pending.chunks(BATCH_SIZE) {
+            update_pb(
+                pb,
+                format!(
+                    "Getting history of {} repos via GraphQL, page: {}",
+                    batch.len(),
+                    page
+                ),
+            );
+
+            let resp = match history_query(batch) {
+                Ok(query) => gh.graphql(url, &query).await,
+                Err(e) => Err(e),
+            };
+            let mut resp = match resp {
+                Ok(resp) => resp,
+                Err(e) => {
+                    for repo in batch {
+                        res.insert(repo.full_name.clone(), Err(eyre!("{e}")));
+                    }
+                    continue;
+                }
+            };
+
+            for (i, repo) in batch.iter().enumerate() {
+                let alias = format!("r{i}");
+                let errors = resp
+                    .errors
+                    .iter()
+                    .filter(|e| e.path.first().and_then(|p| p.as_str()) == Some(alias.as_str()))
+                    .map(|e| e.message.as_str())
+                    .collect::<Vec<_>>();
+                if !errors.is_empty() {
+                    res.insert(
+                        repo.full_name.clone(),
+                        Err(eyre!("GraphQL query failed: {}", errors.join("; "))),
+                    );
+                    continue;
+                }
+
+                let history =
+                    match serde_json::from_value::<Option<Repository>>(resp.data[&alias].take()) {
+                        Ok(repository) => repository
+                            .and_then(|r| r.default_branch_ref)
+                            .and_then(|r| r.target)
+                            .map(|t| t.history),
+                        Err(e) => {
+                            res.insert(repo.full_name.clone(), Err(e.into()));
+                            continue;
+                        }
+                    };
+
+                let commits = res
+                    .entry(repo.full_name.clone())
+                    .or_insert_with(|| Ok(vec![]));
+                // Empty repositories have no default branch.
+                let (Some(history), Ok(commits)) = (history, commits) else {
+                    continue;
+                };
+
+                commits.extend(history.nodes.into_iter().map(Commit::from));
+
+                if history.page_info.has_next_page {
+                    next.push(Pending {
+                        full_name: repo.full_name.clone(),
+                        window: repo.window,
+                        cursor: history.page_info.end_cursor,
+                    });
+                }
+            }
+        }
+
+        pending = next;
+        page += 1;
+    }
+
+    res
+}
+
+fn history_query(batch: &[Pending]) -> Result<String> {
+    let mut query = String::from("query {\n");
+
+    for (i, repo) in batch.iter().enumerate() {
+        let (owner, name) = repo
+            .full_name
+            .split_once('/')
+            .ok_or_else(|| eyre!("{} is not a full repository name", repo.full_name))?;
+        let after = match &repo.cursor {
+            Some(cursor) => format!(", after: {}", serde_json::to_string(cursor)?),
+            None => String::new(),
+        };
+
+        query.push_str(&format!(
+            r#"r{i}: repository(owner: {owner}, name: {name}) {{
+  defaultBranchRef {{ target {{ ... on Commit {{
+    history(since: "{since}", until: "{until}", first: 100{after}) {{
+      pageInfo {{ hasNextPage endCursor }}
+      nodes {{
+        oid
+        message
+        author {{ name email date user {{ login url }} }}
+        committer {{ name email date user {{ login url }} }}
+      }}
+    }}
+  }} }} }}
+}}
+"#,
+            owner = serde_json::to_string(owner)?,
+            name = serde_json::to_string(name)?,
+            since = repo.window.since.to_rfc3339_opts(SecondsFormat::Secs, true),
+            until = repo.window.until.to_rfc3339_opts(SecondsFormat::Secs, true),
+        ));
+    }
+
+    query.push('}');
+
+    Ok(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+    use crate::cache::CacheMode;
+
+    #[tokio::test]
+    async fn keeps_the_repos_that_did_not_fail() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": {
+                    "r0": null,
+                    "r1": { "defaultBranchRef": { "target": { "history": {
+                        "pageInfo": { "hasNextPage": false, "endCursor": null },
+                        "nodes": [{
+                            "oid": "abc",
+                            "author": { "name": "A", "email": "a@x", "date": "2026-06-02T00:00:00Z", "user": null },
+                            "committer": { "name": "A", "email": "a@x", "date": "2026-06-02T00:00:00Z", "user": null }
+                        }]
+                    } } } }
+                },
+                "errors": [{ "type": "FORBIDDEN", "path": ["r0"], "message": "Resource not accessible" }]
+            })))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let gh = ApiClient::new(String::new(), CacheMode::Normal, dir.path(), None).unwrap();
+        let window = Window::new(Some(30), None, Some(Utc::now())).unwrap();
+        let mut res = get_commits(
+            &gh,
+            &format!("{}/graphql", server.uri()),
+            vec![
+                ("x/private".to_string(), window),
+                ("x/public".to_string(), window),
+            ],
+            None,
+        )
+        .await;
+
+        assert!(res.remove("x/private").unwrap().is_err());
+        let commits = res.remove("x/public").unwrap().unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].sha, "abc");
+    }
+
+    #[test]
+    fn recognizes_github_apps_by_email() {
+        let commit: HistoryCommit = serde_json::from_value(json!({
+            "oid": "abc",
+            "author": {
+                "name": "dependabot[bot]",
+                "email": "49699333+dependabot[bot]@users.noreply.github.com",
+                "date": "2026-06-02T00:00:00Z",
+                "user": null
+            },
+            "committer": {
+                "name": "Jane",
+                "email": "jane@example.com",
+                "date": "2026-06-02T00:00:00Z",
+                "user": null
+            }
+        }))
+        .unwrap();
+        let commit = Commit::from(commit);
+
+        let author = commit.author.unwrap();
+        assert_eq!(author.login.as_deref(), Some("dependabot[bot]"));
+        assert_eq!(
+            author.html_url.as_deref(),
+            Some("https://github.com/apps/dependabot")
+        );
+        assert_eq!(author.user_type.as_deref(), Some("Bot"));
+        assert!(commit.committer.is_none());
+    }
+}
@@ -0,0 +1,139 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use eyre::{bail, eyre, Result};
+
+/// Canonical logins of people known under several logins or emails, read from
+/// a mailmap-style file.
+///
+/// Each line starts with the canonical login, followed by any number of
+/// `<email>` and `@login` aliases, `#` starts a comment:
+///
+/// ```text
+/// alice <alice@example.com> <alice@work.example> @alice-old
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Identities {
+    logins: HashMap<String, String>,
+    /// Keyed by lowercase email
+    emails: HashMap<String, String>,
+}
+
+impl Identities {
+    pub fn load(path: &Path) -> Result<Self> {
+        let s = fs::read_to_string(path)
+            .map_err(|e| eyre!("Failed to read {}: {e}", path.display()))?;
+
+        Self::parse(&s).map_err(|e| eyre!("{}: {e}", path.display()))
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut res = Self::default();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let end = line.find(['<', '@']).unwrap_or(line.len());
+            let canonical = line[..end].trim();
+            if canonical.is_empty() {
+                bail!("line {}: missing the canonical login", i + 1);
+            }
+
+            let mut rest = line[end..].trim_start();
+            while !rest.is_empty() {
+                if let Some(email) = rest.strip_prefix('<') {
+                    let (email, tail) = email
+                        .split_once('>')
+                        .ok_or_else(|| eyre!("line {}: unclosed <", i + 1))?;
+                    res.emails
+                        .insert(email.trim().to_lowercase(), canonical.to_string());
+                    rest = tail;
+                } else if let Some(login) = rest.strip_prefix('@') {
+                    let end = login
+                        .find(|c: char| c.is_whitespace() || c == '<')
+                        .unwrap_or(login.len());
+                    res.logins
+                        .insert(login[..end].to_string(), canonical.to_string());
+                    rest = &login[end..];
+                } else {
+                    bail!("line {}: expected <email> or @login, found {rest}", i + 1);
+                }
+
+                rest = rest.trim_start();
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// The canonical login of `email`, if listed.
+    pub fn resolve_email(&self, email: &str) -> Option<&str> {
+        self.emails.get(&email.to_lowercase()).map(|s| s.as_str())
+    }
+
+    /// The canonical login of someone known as `login` or `email`, `None` if
+    /// neither is known and there is no login to fall back to.
+    pub fn resolve<'a>(&'a self, login: Option<&'a str>, email: Option<&str>) -> Option<&'a str> {
+        email
+            .filter(|e| !e.is_empty())
+            .and_then(|e| self.resolve_email(e))
+            .or_else(|| login.and_then(|l| self.logins.get(l)).map(|s| s.as_str()))
+            .or(login)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_aliases() {
+        let identities = Identities::parse(
+            "# canonical login, then aliases\n\
+             alice <Alice@Example.com> <alice@work.example> @alice-old # moved\n\
+             \n\
+             bob@bob-old<bob@example.com>\n",
+        )
+        .unwrap();
+
+        assert_eq!(identities.resolve_email("alice@example.com"), Some("alice"));
+        assert_eq!(
+            identities.resolve_email("ALICE@WORK.EXAMPLE"),
+            Some("alice")
+        );
+        assert_eq!(identities.resolve(Some("alice-old"), None), Some("alice"));
+        assert_eq!(identities.resolve(Some("bob-old"), None), Some("bob"));
+        assert_eq!(
+            identities.resolve(None, Some("bob@example.com")),
+            Some("bob")
+        );
+
+        // Emails win over logins, unknown logins stay as they are.
+        assert_eq!(
+            identities.resolve(Some("alice-old"), Some("bob@example.com")),
+            Some("bob")
+        );
+        assert_eq!(identities.resolve(Some("carol"), Some("")), Some("carol"));
+        assert_eq!(identities.resolve(None, Some("carol@example.com")), None);
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        let err = |s: &str| Identities::parse(s).unwrap_err().to_string();
+
+        assert_eq!(
+            err("alice <alice@example.com>\nbob <bob@example.com"),
+            "line 2: unclosed <"
+        );
+        assert_eq!(
+            err("<alice@example.com> @alice-old"),
+            "line 1: missing the canonical login"
+        );
+        assert_eq!(
+            err("alice <alice@example.com> alice-old"),
+            "line 1: expected <email> or @login, found alice-old"
+        );
+    }
+}
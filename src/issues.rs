@@ -0,0 +1,86 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use eyre::Result;
+use indicatif::ProgressBar;
+use serde::Deserialize;
+
+use crate::{client::ApiClient, Author, Window};
+
+#[derive(Deserialize, Debug)]
+pub struct Issue {
+    pub number: u64,
+    pub user: Option<Author>,
+    pub created_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+    /// Only present when fetching a single issue
+    #[serde(default)]
+    pub closed_by: Option<Author>,
+    /// Present if this issue is a pull request
+    #[serde(default)]
+    pull_request: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct IssueComment {
+    pub user: Option<Author>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Issues and comments of a repository updated inside the query interval.
+#[derive(Debug)]
+pub struct IssueActivity {
+    /// Pull requests are left out
+    pub issues: Vec<Issue>,
+    /// Comments on both issues and pull requests
+    pub comments: Vec<IssueComment>,
+}
+
+pub async fn get_issues(
+    gh: &ApiClient,
+    repo_api_url: &str,
+    window: Window,
+    pb: Option<&ProgressBar>,
+) -> Result<IssueActivity> {
+    let since = window.since.to_rfc3339_opts(SecondsFormat::Secs, true);
+
+    let listed = gh
+        .get_paginated::<Issue, _>(
+            format!(
+                "{}/issues?state=all&since={}&per_page=100",
+                repo_api_url, since
+            ),
+            pb,
+            |_| Ok(true),
+        )
+        .await?;
+
+    let mut issues = vec![];
+
+    for issue in listed {
+        if issue.pull_request.is_some() {
+            continue;
+        }
+
+        // Who closed an issue is only part of the single issue response.
+        if issue.closed_at.is_some_and(|d| window.contains(d)) {
+            issues.push(
+                gh.get_json(&format!("{}/issues/{}", repo_api_url, issue.number))
+                    .await?,
+            );
+        } else {
+            issues.push(issue);
+        }
+    }
+
+    let comments = gh
+        .get_paginated(
+            format!(
+                "{}/issues/comments?since={}&per_page=100",
+                repo_api_url, since
+            ),
+            pb,
+            |_| Ok(true),
+        )
+        .await?;
+
+    Ok(IssueActivity { issues, comments })
+}
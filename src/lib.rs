@@ -0,0 +1,157 @@
+//! Count who contributed to the repositories of an organization during a time interval.
+//!
+//! A [`ContributionSource`] lists repositories, commits and members of an organization,
+//! [`Contributors`] aggregates them per login, and the resulting [`Report`] is printed
+//! by [`output::print_report`].
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use eyre::{bail, eyre, Result};
+use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+pub mod cache;
+pub mod client;
+pub mod config;
+pub mod filter;
+pub mod gitea;
+pub mod github;
+pub mod gitlab;
+pub mod graphql;
+pub mod identity;
+pub mod issues;
+pub mod local;
+pub mod output;
+pub mod pulls;
+pub mod source;
+pub mod stats;
+pub mod teams;
+pub mod trailer;
+
+pub use identity::Identities;
+pub use source::ContributionSource;
+pub use stats::{ContributorStats, Contributors, Membership, Report, SortBy};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Repo {
+    /// API URL of this repository
+    pub url: String,
+    /// `owner/name`
+    pub full_name: String,
+    /// `None` for empty repositories, or when unknown
+    pub pushed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub fork: bool,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub is_template: bool,
+    #[serde(default)]
+    pub topics: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Commit {
+    pub sha: String,
+    pub commit: Option<RepoCommit>,
+    pub author: Option<Author>,
+    pub committer: Option<Author>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RepoCommit {
+    pub author: RepoAuthor,
+    pub committer: RepoAuthor,
+    /// Empty for commits cached before messages were kept
+    #[serde(default)]
+    pub message: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RepoAuthor {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub email: String,
+    pub date: DateTime<Utc>,
+}
+
+/// Lines changed in a single file by a commit.
+#[derive(Deserialize, Debug)]
+pub struct FileChange {
+    pub filename: String,
+    pub additions: u64,
+    pub deletions: u64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Branch {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Author {
+    pub login: Option<String>,
+    pub html_url: Option<String>,
+    /// `User`, `Bot` or `Organization` on GitHub, `None` elsewhere
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub user_type: Option<String>,
+}
+
+/// The time interval a report covers.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Window {
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+}
+
+impl Window {
+    /// `until` defaults to now, `since` to `days` before `until`.
+    pub fn new(
+        days: Option<u64>,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<Self> {
+        let until = until.unwrap_or_else(Utc::now);
+        let since = match (since, days) {
+            (Some(since), _) => since,
+            (None, Some(days)) => i64::try_from(days)
+                .ok()
+                .and_then(ChronoDuration::try_days)
+                .and_then(|days| until.checked_sub_signed(days))
+                .ok_or_else(|| eyre!("--days {days} is out of range"))?,
+            (None, None) => bail!("Either --days or --since must be set"),
+        };
+
+        if since > until {
+            bail!("--since {since} is later than --until {until}");
+        }
+
+        Ok(Self { since, until })
+    }
+
+    pub fn contains(&self, dt: DateTime<Utc>) -> bool {
+        self.since <= dt && dt <= self.until
+    }
+}
+
+pub fn update_pb(pb: Option<&ProgressBar>, msg: String) {
+    if let Some(pb) = pb {
+        pb.set_message(msg);
+    } else {
+        info!("{}", msg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_days_out_of_range() {
+        assert!(Window::new(Some(100_000_000), None, None).is_err());
+        assert!(Window::new(Some(u64::MAX), None, None).is_err());
+        let window = Window::new(Some(31), None, None).unwrap();
+        assert_eq!(window.until - window.since, ChronoDuration::days(31));
+    }
+}
@@ -0,0 +1,389 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use eyre::{eyre, Result};
+use git2::{Oid, Patch, Repository, Revwalk, Signature, Sort};
+use tracing::debug;
+
+use crate::{
+    source::ContributionSource, Author, Commit, FileChange, Membership, Repo, RepoAuthor,
+    RepoCommit, Window,
+};
+
+/// What a [`LocalSource`] scans.
+#[derive(Debug, Clone)]
+pub struct LocalOptions {
+    /// Used as the owner part of the repository full names
+    pub org: String,
+    /// Directory whose subdirectories are git clones, bare or not
+    pub dir: PathBuf,
+    /// Revisions or ref globs to walk, e.g. `main` or `refs/remotes/origin/*`,
+    /// `HEAD` when empty
+    pub refs: Vec<String>,
+    /// Walk every local and remote-tracking branch
+    pub all_branches: bool,
+}
+
+/// Contributions to local clones, without any network access.
+///
+/// Commits carry no accounts, so contributors are identified by the name in
+/// the commit and linked to their email.
+pub struct LocalSource {
+    options: LocalOptions,
+}
+
+impl LocalSource {
+    pub fn new(options: LocalOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl ContributionSource for LocalSource {
+    async fn list_repos(&self, window: Window) -> Result<Vec<Repo>> {
+        let options = self.options.clone();
+        tokio::task::spawn_blocking(move || scan_repos(&options, window)).await?
+    }
+
+    async fn list_commits(&self, repo: &Repo, window: Window) -> Result<Vec<Commit>> {
+        let options = self.options.clone();
+        let path = PathBuf::from(&repo.url);
+        tokio::task::spawn_blocking(move || walk_commits(&options, &path, window)).await?
+    }
+
+    async fn commit_files(&self, repo: &Repo, sha: &str) -> Result<Vec<FileChange>> {
+        let path = PathBuf::from(&repo.url);
+        let sha = sha.to_string();
+        tokio::task::spawn_blocking(move || diff_files(&path, &sha)).await?
+    }
+
+    /// Clones know nothing about organizations, everyone is external.
+    async fn membership(&self) -> Result<HashMap<String, Membership>> {
+        Ok(HashMap::new())
+    }
+}
+
+fn scan_repos(options: &LocalOptions, window: Window) -> Result<Vec<Repo>> {
+    let mut res = vec![];
+
+    for entry in fs::read_dir(&options.dir)
+        .map_err(|e| eyre!("Failed to read {}: {e}", options.dir.display()))?
+    {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        let Ok(repo) = Repository::open(&path) else {
+            debug!("Skipping {}, not a git repository", path.display());
+            continue;
+        };
+
+        let Some(name) = path.file_name().and_then(|s| s.to_str()) else {
+            continue;
+        };
+
+        // The newest tip stands in for the push time, to skip idle clones early.
+        let mut walk = revwalk(&repo, options)?;
+        walk.set_sorting(Sort::TIME)?;
+        let pushed_at = match walk.next() {
+            Some(id) => Some(time(&repo.find_commit(id?)?.committer())),
+            None => None,
+        };
+
+        if pushed_at.is_some_and(|d| d >= window.since) {
+            res.push(Repo {
+                url: path.to_string_lossy().into_owned(),
+                full_name: format!("{}/{}", options.org, name.trim_end_matches(".git")),
+                pushed_at,
+                fork: false,
+                archived: false,
+                is_template: false,
+                topics: vec![],
+            });
+        }
+    }
+
+    Ok(res)
+}
+
+fn walk_commits(options: &LocalOptions, path: &Path, window: Window) -> Result<Vec<Commit>> {
+    let repo = Repository::open(path)?;
+    let mut res = vec![];
+
+    // Commit dates are not monotonic along history, so everything is walked.
+    for id in revwalk(&repo, options)? {
+        let commit = repo.find_commit(id?)?;
+        let (author, committer) = (commit.author(), commit.committer());
+
+        let committed = time(&committer);
+        if !window.contains(committed) {
+            continue;
+        }
+
+        res.push(Commit {
+            sha: commit.id().to_string(),
+            commit: Some(RepoCommit {
+                author: signature(&author, time(&author)),
+                committer: signature(&committer, committed),
+                message: String::from_utf8_lossy(commit.message_bytes()).into_owned(),
+            }),
+            author: identity(&author),
+            committer: identity(&committer),
+        });
+    }
+
+    Ok(res)
+}
+
+/// Lines changed per file by `sha` against its first parent.
+fn diff_files(path: &Path, sha: &str) -> Result<Vec<FileChange>> {
+    let repo = Repository::open(path)?;
+    let commit = repo.find_commit(Oid::from_str(sha)?)?;
+    let parent = match commit.parents().next() {
+        Some(parent) => Some(parent.tree()?),
+        None => None,
+    };
+    let diff = repo.diff_tree_to_tree(parent.as_ref(), Some(&commit.tree()?), None)?;
+
+    let mut res = vec![];
+    for i in 0..diff.deltas().len() {
+        let Some(patch) = Patch::from_diff(&diff, i)? else {
+            continue;
+        };
+        let (_, additions, deletions) = patch.line_stats()?;
+        let Some(filename) = patch.delta().new_file().path().and_then(|p| p.to_str()) else {
+            continue;
+        };
+
+        res.push(FileChange {
+            filename: filename.to_string(),
+            additions: additions as u64,
+            deletions: deletions as u64,
+        });
+    }
+
+    Ok(res)
+}
+
+/// A walk over the refs selected by `options`, each commit visited once.
+fn revwalk<'a>(repo: &'a Repository, options: &LocalOptions) -> Result<Revwalk<'a>> {
+    let mut walk = repo.revwalk()?;
+
+    if options.all_branches {
+        walk.push_glob("refs/heads/*")?;
+        walk.push_glob("refs/remotes/*")?;
+    } else if options.refs.is_empty() {
+        // Empty repositories have no HEAD to walk.
+        if repo.head().is_ok() {
+            walk.push_head()?;
+        }
+    } else {
+        for spec in &options.refs {
+            if spec.contains(['*', '?', '[']) {
+                walk.push_glob(spec)?;
+            } else {
+                let obj = repo.revparse_single(spec).map_err(|e| {
+                    eyre!("--ref {spec} not found in {}: {e}", repo.path().display())
+                })?;
+                walk.push(obj.peel_to_commit()?.id())?;
+            }
+        }
+    }
+
+    Ok(walk)
+}
+
+fn time(sig: &Signature) -> DateTime<Utc> {
+    DateTime::from_timestamp(sig.when().seconds(), 0).unwrap_or_default()
+}
+
+fn signature(sig: &Signature, date: DateTime<Utc>) -> RepoAuthor {
+    RepoAuthor {
+        name: sig.name().unwrap_or_default().to_string(),
+        email: sig.email().unwrap_or_default().to_string(),
+        date,
+    }
+}
+
+fn identity(sig: &Signature) -> Option<Author> {
+    let name = sig.name()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    Some(Author {
+        login: Some(name.to_string()),
+        html_url: Some(format!("mailto:{}", sig.email().unwrap_or_default())),
+        user_type: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use git2::Time;
+
+    use super::*;
+
+    fn day(d: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 6, d, 0, 0, 0).unwrap()
+    }
+
+    fn window(since: u32, until: u32) -> Window {
+        Window {
+            since: day(since),
+            until: day(until),
+        }
+    }
+
+    /// Commit `content` as `a.txt` on `refname`, on top of `parent`.
+    fn commit(repo: &Repository, refname: &str, parent: Option<Oid>, content: &str, d: u32) -> Oid {
+        let sig = Signature::new(
+            "Alice",
+            "alice@example.com",
+            &Time::new(day(d).timestamp(), 0),
+        )
+        .unwrap();
+        let blob = repo.blob(content.as_bytes()).unwrap();
+        let mut tree = repo.treebuilder(None).unwrap();
+        tree.insert("a.txt", blob, 0o100644).unwrap();
+        let tree = repo.find_tree(tree.write().unwrap()).unwrap();
+        let parents = parent
+            .map(|p| repo.find_commit(p).unwrap())
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        repo.commit(
+            Some(refname),
+            &sig,
+            &sig,
+            &format!("Day {d}"),
+            &tree,
+            &parents.iter().collect::<Vec<_>>(),
+        )
+        .unwrap()
+    }
+
+    /// A clone `app` whose `main` has commits on June 1st and 10th, and whose
+    /// `topic` branch adds one on the 11th.
+    fn fixture() -> (tempfile::TempDir, [Oid; 3]) {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init_bare(dir.path().join("app.git")).unwrap();
+        repo.set_head("refs/heads/main").unwrap();
+
+        let first = commit(&repo, "refs/heads/main", None, "one\n", 1);
+        let second = commit(
+            &repo,
+            "refs/heads/main",
+            Some(first),
+            "one\ntwo\nthree\n",
+            10,
+        );
+        let topic = commit(&repo, "refs/heads/topic", Some(second), "three\n", 11);
+
+        fs::create_dir(dir.path().join("notes")).unwrap();
+
+        (dir, [first, second, topic])
+    }
+
+    fn options(dir: &Path) -> LocalOptions {
+        LocalOptions {
+            org: "org".to_string(),
+            dir: dir.to_path_buf(),
+            refs: vec![],
+            all_branches: false,
+        }
+    }
+
+    fn shas(options: &LocalOptions, window: Window) -> Vec<String> {
+        let mut res = walk_commits(options, &options.dir.join("app.git"), window)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.sha)
+            .collect::<Vec<_>>();
+        res.sort();
+        res
+    }
+
+    fn sorted(ids: &[Oid]) -> Vec<String> {
+        let mut res = ids.iter().map(|i| i.to_string()).collect::<Vec<_>>();
+        res.sort();
+        res
+    }
+
+    #[test]
+    fn scans_clones_active_in_the_window() {
+        let (dir, _) = fixture();
+        let options = options(dir.path());
+
+        let repos = scan_repos(&options, window(5, 20)).unwrap();
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].full_name, "org/app");
+        assert_eq!(repos[0].pushed_at, Some(day(10)));
+
+        assert!(scan_repos(&options, window(15, 20)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn walks_head_inside_the_window() {
+        let (dir, [first, second, _]) = fixture();
+        let options = options(dir.path());
+
+        assert_eq!(shas(&options, window(5, 20)), sorted(&[second]));
+        assert_eq!(shas(&options, window(1, 20)), sorted(&[first, second]));
+
+        let commits = walk_commits(&options, &dir.path().join("app.git"), window(5, 20)).unwrap();
+        let author = commits[0].author.as_ref().unwrap();
+        assert_eq!(author.login.as_deref(), Some("Alice"));
+        assert_eq!(author.html_url.as_deref(), Some("mailto:alice@example.com"));
+    }
+
+    #[test]
+    fn walks_the_selected_refs() {
+        let (dir, [_, second, topic]) = fixture();
+
+        let mut by_name = options(dir.path());
+        by_name.refs = vec!["topic".to_string()];
+        assert_eq!(shas(&by_name, window(5, 20)), sorted(&[second, topic]));
+
+        let mut by_glob = options(dir.path());
+        by_glob.refs = vec!["refs/heads/t*".to_string()];
+        assert_eq!(shas(&by_glob, window(5, 20)), sorted(&[second, topic]));
+
+        let mut all = options(dir.path());
+        all.all_branches = true;
+        assert_eq!(shas(&all, window(5, 20)), sorted(&[second, topic]));
+    }
+
+    #[test]
+    fn rejects_unknown_refs() {
+        let (dir, _) = fixture();
+        let mut typo = options(dir.path());
+        typo.refs = vec!["mian".to_string()];
+
+        let err = walk_commits(&typo, &dir.path().join("app.git"), window(5, 20)).unwrap_err();
+        assert!(err.to_string().contains("--ref mian"));
+    }
+
+    #[test]
+    fn diffs_against_the_first_parent() {
+        let (dir, [first, second, topic]) = fixture();
+        let path = dir.path().join("app.git");
+
+        let changes = |id: Oid| {
+            diff_files(&path, &id.to_string())
+                .unwrap()
+                .into_iter()
+                .map(|f| (f.filename, f.additions, f.deletions))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(changes(first), [("a.txt".to_string(), 1, 0)]);
+        assert_eq!(changes(second), [("a.txt".to_string(), 2, 0)]);
+        assert_eq!(changes(topic), [("a.txt".to_string(), 0, 2)]);
+    }
+}
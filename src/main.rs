@@ -1,70 +1,345 @@
-use std::{collections::HashMap, time::Duration};
-
-use chrono::{DateTime, Duration as ChronoDuration, Utc};
-use clap::Parser;
-use eyre::{bail, Result};
-use futures::StreamExt;
-use indicatif::ProgressBar;
-use reqwest::{Client, StatusCode};
-use serde::Deserialize;
-use tracing::{debug, error, info, level_filters::LevelFilter};
+use std::{path::PathBuf, time::Duration};
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use clap::{
+    parser::ValueSource, ArgAction, ArgMatches, Command, CommandFactory, FromArgMatches, Parser,
+    ValueEnum,
+};
+use eyre::{bail, eyre, Result};
+use indicatif::{ProgressBar, ProgressStyle};
+use kpi::{
+    cache::{self, CacheMode},
+    config::{Config, Profile},
+    filter::{BotFilter, PathFilter, RepoFilter},
+    gitea::{GiteaOptions, GiteaSource},
+    github::{Api, GitHubOptions, GitHubSource, GITHUB_API_URL},
+    gitlab::{GitLabOptions, GitLabSource, GITLAB_API_URL},
+    local::{LocalOptions, LocalSource},
+    output::{self, Format},
+    source::{self, CommitOptions, LineOptions},
+    trailer::Trailer,
+    update_pb, ContributionSource, Contributors, Identities, Report, SortBy, Window,
+};
+use tracing::{debug, info, level_filters::LevelFilter};
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
 
-#[derive(Deserialize, Debug)]
-struct Repo {
-    url: String,
-    pushed_at: String,
-}
-
-#[derive(Deserialize, Debug)]
-struct Commit {
-    commit: Option<RepoCommit>,
-    author: Option<Author>,
-    committer: Option<Author>,
-}
-
-#[derive(Deserialize, Debug)]
-struct RepoCommit {
-    author: RepoAuthor,
-    committer: RepoAuthor,
-}
-
-#[derive(Deserialize, Debug)]
-struct RepoAuthor {
-    date: String,
-}
-
-#[derive(Deserialize, Debug)]
-struct Author {
-    login: Option<String>,
-    html_url: Option<String>,
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Source {
+    /// A GitHub organization
+    #[default]
+    Github,
+    /// A GitLab group and its subgroups
+    Gitlab,
+    /// A Gitea or Forgejo organization, requires `--api-url`
+    Gitea,
+    /// Local clones under `--repos-dir`, without network access
+    Local,
 }
 
 #[derive(Parser, Debug)]
 #[clap(about, version, author)]
 struct Args {
-    /// result output to markdown format
+    /// Configuration file, defaults to `./kpi.toml` or `$XDG_CONFIG_HOME/kpi/kpi.toml`
     #[arg(long)]
+    config: Option<PathBuf>,
+    /// Use this profile of the configuration file on top of its defaults
+    #[arg(long)]
+    profile: Option<String>,
+    /// Output format
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
+    /// result output to markdown format, same as `--format markdown`
+    #[arg(long, hide = true, conflicts_with = "format")]
     to_markdown: bool,
-    /// Github token
-    #[arg(long, env = "GITHUB_TOKEN")]
-    token: String,
-    /// Days for query kpi
+    /// Where the organization is hosted
+    #[arg(long, value_enum, default_value_t)]
+    source: Source,
+    /// Base URL of the REST API, defaults to the public instance of `--source`
+    #[arg(long, env = "KPI_API_URL")]
+    api_url: Option<String>,
+    /// API token, falls back to `$GITHUB_TOKEN`, `$GITLAB_TOKEN` or `$GITEA_TOKEN` depending on `--source`
     #[arg(long)]
-    days: u64,
+    token: Option<String>,
+    /// Days for query kpi
+    #[arg(long, conflicts_with = "since")]
+    days: Option<u64>,
+    /// Start of the query interval (RFC3339 or YYYY-MM-DD)
+    #[arg(long, value_parser = parse_since)]
+    since: Option<DateTime<Utc>>,
+    /// End of the query interval (RFC3339 or YYYY-MM-DD, inclusive), defaults to now
+    #[arg(long, value_parser = parse_until)]
+    until: Option<DateTime<Utc>>,
     /// Filter is organization user
     #[arg(long)]
     filter_org_user: bool,
-    /// Organization name
+    /// Also recognize outside collaborators of the organization (requires an org owner token)
+    #[arg(long)]
+    outside_collaborators: bool,
+    /// Directory of git clones scanned by `--source local`
+    #[arg(long)]
+    repos_dir: Option<PathBuf>,
+    /// Revision or ref glob walked by `--source local` instead of `HEAD`, can be repeated
+    #[arg(long = "ref", conflicts_with = "all_branches")]
+    refs: Vec<String>,
+    /// Organization name, or full path of the GitLab group, can be repeated
+    #[arg(long)]
+    org: Vec<String>,
+    /// Also count this repository (`owner/name`) outside of `--org`, can be repeated
+    #[arg(long)]
+    repo: Vec<String>,
+    /// Only count repos whose name matches this glob, can be repeated
+    #[arg(long)]
+    include_repo: Vec<String>,
+    /// Skip repos whose name matches this glob, can be repeated
+    #[arg(long)]
+    exclude_repo: Vec<String>,
+    /// Only count repos with this topic, can be repeated to allow any of them
+    #[arg(long)]
+    topic: Vec<String>,
+    /// Skip forked repos
+    #[arg(long)]
+    no_forks: bool,
+    /// Skip archived repos
     #[arg(long)]
-    org: String,
+    no_archived: bool,
+    /// Skip template repos
+    #[arg(long)]
+    no_templates: bool,
+    /// API used to fetch commits
+    #[arg(long, value_enum, default_value_t, conflicts_with = "all_branches")]
+    api: Api,
+    /// Count commits on every branch instead of only the default branch
+    #[arg(long)]
+    all_branches: bool,
+    /// Also count opened, merged, closed and reviewed pull requests
+    #[arg(long)]
+    pulls: bool,
+    /// Also count opened and closed issues, and comments
+    #[arg(long)]
+    issues: bool,
+    /// Also credit people named in these commit message trailers, comma separated
+    #[arg(long, value_enum, value_delimiter = ',')]
+    trailers: Vec<Trailer>,
+    /// Also count lines added and deleted, with one more request per commit
+    #[arg(long)]
+    lines: bool,
+    /// Ignore changes to files matching this glob when counting lines, can be repeated
+    #[arg(long)]
+    exclude_path: Vec<String>,
+    /// Count at most this many added, and as many deleted lines per commit
+    #[arg(long)]
+    max_lines_per_commit: Option<u64>,
+    /// Report logins or emails matching this glob as automation, can be repeated.
+    /// `[bot]` accounts and users of type Bot always are
+    #[arg(long)]
+    bot: Vec<String>,
+    /// Also break the report down by GitHub team, with totals per team
+    #[arg(long)]
+    teams: bool,
+    /// Only report members of this team (`slug` or `org/slug`), implies `--teams`
+    #[arg(long)]
+    team: Option<String>,
+    /// Also list the repositories of each team with its permission, implies `--teams`
+    #[arg(long)]
+    team_repos: bool,
+    /// Mailmap-style file mapping emails and logins to canonical logins
+    #[arg(long)]
+    mailmap: Option<PathBuf>,
+    /// Sort contributors by this column
+    #[arg(long, value_enum, default_value_t)]
+    sort_by: SortBy,
     /// Set fetch network thread
     #[arg(long, default_value = "4")]
     thread: usize,
+    /// Fetch every commit again instead of reusing the local cache
+    #[arg(long, conflicts_with = "offline")]
+    refresh: bool,
+    /// Only report commits from the local cache, without any network access
+    #[arg(long)]
+    offline: bool,
     /// Do not display fetch progress
+    #[arg(long)]
     no_progress: bool,
 }
 
+fn parse_date(s: &str, time: NaiveTime) -> std::result::Result<DateTime<Utc>, String> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.to_utc());
+    }
+
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|d| d.and_time(time).and_utc())
+        .map_err(|_| format!("{s} is not a RFC3339 or YYYY-MM-DD date"))
+}
+
+fn parse_since(s: &str) -> std::result::Result<DateTime<Utc>, String> {
+    parse_date(s, NaiveTime::MIN)
+}
+
+fn parse_until(s: &str) -> std::result::Result<DateTime<Utc>, String> {
+    // A bare date includes the whole day.
+    parse_date(s, NaiveTime::from_hms_opt(23, 59, 59).unwrap())
+}
+
+/// Let every flag take an optional value, so that `--pulls=false` turns off
+/// what the configuration file turned on.
+fn negatable_flags(cmd: Command) -> Command {
+    cmd.mut_args(|arg| match arg.get_action() {
+        ArgAction::SetTrue => arg
+            .action(ArgAction::Set)
+            .num_args(0..=1)
+            .require_equals(true)
+            .default_value("false")
+            .default_missing_value("true"),
+        _ => arg,
+    })
+}
+
+impl Args {
+    /// Take the settings of `profile` which were not given on the command line
+    /// or through the environment.
+    fn apply(&mut self, profile: Profile, matches: &ArgMatches) -> Result<()> {
+        let explicit = |id: &str| {
+            matches!(
+                matches.value_source(id),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            )
+        };
+
+        fn layer<T>(explicit: bool, arg: &mut T, value: Option<T>) {
+            if let (false, Some(value)) = (explicit, value) {
+                *arg = value;
+            }
+        }
+
+        fn parse_enum<T: ValueEnum>(key: &str, value: Option<String>) -> Result<Option<T>> {
+            value
+                .map(|v| T::from_str(&v, true).map_err(|e| eyre!("Invalid {key} {v}: {e}")))
+                .transpose()
+        }
+
+        // The window is set as a whole, `--since` must not be combined with `days`.
+        let window = explicit("days") || explicit("since");
+        let since = profile
+            .since
+            .map(|s| parse_since(&s))
+            .transpose()
+            .map_err(|e| eyre!(e))?;
+        let until = profile
+            .until
+            .map(|s| parse_until(&s))
+            .transpose()
+            .map_err(|e| eyre!(e))?;
+        layer(window, &mut self.days, profile.days.map(Some));
+        layer(window, &mut self.since, since.map(Some));
+        layer(explicit("until"), &mut self.until, until.map(Some));
+
+        layer(
+            explicit("source"),
+            &mut self.source,
+            parse_enum("source", profile.source)?,
+        );
+        layer(
+            explicit("format") || self.to_markdown,
+            &mut self.format,
+            parse_enum("format", profile.format)?,
+        );
+        layer(
+            explicit("sort_by"),
+            &mut self.sort_by,
+            parse_enum("sort_by", profile.sort_by)?,
+        );
+        let trailers = profile
+            .trailers
+            .map(|t| {
+                t.into_iter()
+                    .filter_map(|t| parse_enum("trailer", Some(t)).transpose())
+                    .collect::<Result<Vec<Trailer>>>()
+            })
+            .transpose()?;
+        layer(explicit("trailers"), &mut self.trailers, trailers);
+
+        layer(
+            explicit("api_url"),
+            &mut self.api_url,
+            profile.api_url.map(Some),
+        );
+        layer(explicit("org"), &mut self.org, profile.org);
+        layer(explicit("repo"), &mut self.repo, profile.repo);
+        layer(
+            explicit("repos_dir"),
+            &mut self.repos_dir,
+            profile.repos_dir.map(Some),
+        );
+        layer(explicit("thread"), &mut self.thread, profile.thread);
+        layer(
+            explicit("filter_org_user"),
+            &mut self.filter_org_user,
+            profile.filter_org_user,
+        );
+        layer(
+            explicit("outside_collaborators"),
+            &mut self.outside_collaborators,
+            profile.outside_collaborators,
+        );
+        layer(
+            explicit("include_repo"),
+            &mut self.include_repo,
+            profile.include_repo,
+        );
+        layer(
+            explicit("exclude_repo"),
+            &mut self.exclude_repo,
+            profile.exclude_repo,
+        );
+        layer(explicit("topic"), &mut self.topic, profile.topic);
+        layer(explicit("no_forks"), &mut self.no_forks, profile.no_forks);
+        layer(
+            explicit("no_archived"),
+            &mut self.no_archived,
+            profile.no_archived,
+        );
+        layer(
+            explicit("no_templates"),
+            &mut self.no_templates,
+            profile.no_templates,
+        );
+        layer(
+            explicit("all_branches"),
+            &mut self.all_branches,
+            profile.all_branches,
+        );
+        layer(explicit("pulls"), &mut self.pulls, profile.pulls);
+        layer(explicit("issues"), &mut self.issues, profile.issues);
+        layer(explicit("lines"), &mut self.lines, profile.lines);
+        layer(
+            explicit("exclude_path"),
+            &mut self.exclude_path,
+            profile.exclude_path,
+        );
+        layer(
+            explicit("max_lines_per_commit"),
+            &mut self.max_lines_per_commit,
+            profile.max_lines_per_commit.map(Some),
+        );
+        layer(explicit("bot"), &mut self.bot, profile.bot);
+        layer(explicit("teams"), &mut self.teams, profile.teams);
+        layer(explicit("team"), &mut self.team, profile.team.map(Some));
+        layer(
+            explicit("team_repos"),
+            &mut self.team_repos,
+            profile.team_repos,
+        );
+        layer(
+            explicit("mailmap"),
+            &mut self.mailmap,
+            profile.mailmap.map(Some),
+        );
+
+        Ok(())
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenvy::dotenv().ok();
@@ -72,272 +347,362 @@ async fn main() -> Result<()> {
 
     if let Ok(filter) = env_log {
         tracing_subscriber::registry()
-            .with(fmt::layer().with_filter(filter))
+            .with(
+                fmt::layer()
+                    .with_writer(std::io::stderr)
+                    .with_filter(filter),
+            )
             .init();
     } else {
         tracing_subscriber::registry()
-            .with(fmt::layer())
+            .with(fmt::layer().with_writer(std::io::stderr))
             .with(LevelFilter::INFO)
             .init();
     }
-    let args = Args::parse();
-    let Args {
-        to_markdown,
-        token,
-        days,
-        filter_org_user,
-        org,
-        thread,
-        no_progress,
-    } = args;
-
-    let days = days as i64;
-    let days_duration = ChronoDuration::days(days);
-
-    let mut map = HashMap::new();
-
-    let client = Client::builder().user_agent("aosc-kpi").build()?;
-
-    let pb = if !no_progress {
-        let pb = ProgressBar::new_spinner();
-        pb.enable_steady_tick(Duration::from_millis(100));
-        Some(ProgressBar::new_spinner())
-    } else {
-        None
-    };
-
-    update_pb(pb.as_ref(), "Getting matches repos ...".to_string());
-    let repos = get_repos(&client, &token, &org).await?;
+    let matches = negatable_flags(Args::command()).get_matches();
+    let mut args = Args::from_arg_matches(&matches)?;
 
-    let mut filter_repos = vec![];
-
-    for i in repos {
-        let dt = DateTime::parse_from_rfc3339(&i.pushed_at)?.to_utc();
-        if Utc::now() - dt <= days_duration {
-            filter_repos.push(i);
+    match Config::discover(args.config.as_deref())? {
+        Some(config) => {
+            let profile = config.profile(args.profile.as_deref())?;
+            args.apply(profile, &matches)?;
         }
+        None if args.profile.is_some() => bail!("--profile requires a configuration file"),
+        None => {}
     }
 
-    if let Some(ref pb) = pb {
-        pb.println(format!(
-            "A total of {} repos have been modified in the last {} days.",
-            filter_repos.len(),
-            days
-        ));
-    } else {
-        info!(
-            "A total of {} repos have been modified in the last {} days.",
-            filter_repos.len(),
-            days
-        );
+    if args.org.is_empty() && args.repo.is_empty() {
+        bail!("--org or --repo is required, on the command line or in the configuration file");
     }
 
-    debug!("Repos: {:?}", filter_repos);
+    let window = Window::new(args.days, args.since, args.until)?;
+    let format = if args.to_markdown {
+        Format::Markdown
+    } else {
+        args.format
+    };
 
-    let mut tasks = vec![];
+    let mode = if args.offline {
+        CacheMode::Offline
+    } else if args.refresh {
+        CacheMode::Refresh
+    } else {
+        CacheMode::Normal
+    };
 
-    for i in filter_repos {
-        tasks.push(get_commits_info_by_url(
-            &client,
-            i.url,
-            &token,
-            days_duration,
-            pb.as_ref(),
-        ))
+    let token_env = match args.source {
+        Source::Github => "GITHUB_TOKEN",
+        Source::Gitlab => "GITLAB_TOKEN",
+        Source::Gitea => "GITEA_TOKEN",
+        Source::Local => "",
+    };
+    let token = match args.token.clone().or_else(|| std::env::var(token_env).ok()) {
+        Some(token) => token,
+        None if args.offline || args.source == Source::Local => String::new(),
+        None => bail!("--token or ${token_env} is required"),
+    };
+
+    if args.offline && args.issues {
+        // Their URLs embed the window, so they are never cached.
+        bail!("--issues is not available offline");
     }
 
-    let stream = futures::stream::iter(tasks)
-        .buffer_unordered(thread)
-        .collect::<Vec<_>>()
-        .await;
-
-    for i in stream {
-        match i {
-            Ok(commits) => {
-                for commit in commits {
-                    if let Some(author) = commit.author {
-                        if let Some(url) = &author.html_url {
-                            if let Some(login) = author.login {
-                                map.insert(login.to_string(), url.to_string());
-                            }
-                        }
-                    }
-
-                    if let Some(committer) = commit.committer {
-                        if let Some(url) = &committer.html_url {
-                            if let Some(login) = committer.login {
-                                map.insert(login.to_string(), url.to_string());
-                            }
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                error!("{:?}", e);
-            }
+    if args.source != Source::Github {
+        if args.api == Api::Graphql {
+            bail!("--api graphql is only supported by --source github");
         }
-    }
 
-    if filter_org_user {
-        let mut tasks = vec![];
-        for i in map.keys() {
-            tasks.push(is_org_user(&client, i, &token, pb.as_ref()));
+        if args.lines && args.source != Source::Local {
+            bail!("--lines is only supported by --source github and local");
         }
 
-        let stream = futures::stream::iter(tasks)
-            .buffer_unordered(args.thread)
-            .collect::<Vec<_>>()
-            .await;
+        if args.pulls || args.issues {
+            bail!("--pulls and --issues are only supported by --source github");
+        }
 
-        if let Some(pb) = pb {
-            pb.finish_and_clear();
+        if args.teams || args.team.is_some() || args.team_repos {
+            bail!("Teams are only supported by --source github");
         }
 
-        for i in stream {
-            match i {
-                Ok((user, is_org_user)) => {
-                    if !is_org_user {
-                        continue;
-                    }
-
-                    if to_markdown {
-                        println!("- [{}]({})", user, map[user]);
-                    } else {
-                        println!("{user}: {}", map[user]);
-                    }
-                }
-                Err(e) => {
-                    bail!("{e}")
-                }
-            }
+        // Only GitHub commits are kept in the commit cache.
+        if args.offline && args.source != Source::Local {
+            bail!("--offline is only supported by --source github and local");
         }
+    }
+
+    let pb = if !args.no_progress {
+        let pb = ProgressBar::new_spinner()
+            .with_style(ProgressStyle::with_template("{spinner} {prefix} {msg}")?);
+        pb.enable_steady_tick(Duration::from_millis(100));
+        Some(pb)
     } else {
-        if let Some(pb) = pb {
-            pb.finish_and_clear();
+        None
+    };
+
+    let report = match args.source {
+        Source::Github => {
+            let source = GitHubSource::new(
+                token,
+                GitHubOptions {
+                    orgs: args.org.clone(),
+                    repos: args.repo.clone(),
+                    api_url: api_url(&args, GITHUB_API_URL),
+                    api: args.api,
+                    all_branches: args.all_branches,
+                    outside_collaborators: args.outside_collaborators,
+                    mode,
+                    cache_dir: cache::default_dir()?,
+                },
+                pb.clone(),
+            )?;
+
+            collect(&source, &args, window, mode, pb.as_ref()).await?
         }
+        Source::Gitlab => {
+            let source = GitLabSource::new(
+                token,
+                GitLabOptions {
+                    groups: args.org.clone(),
+                    repos: args.repo.clone(),
+                    api_url: api_url(&args, GITLAB_API_URL),
+                    all_branches: args.all_branches,
+                    mode,
+                    cache_dir: cache::default_dir()?,
+                },
+                pb.clone(),
+            )?;
+
+            collect(&source, &args, window, mode, pb.as_ref()).await?
+        }
+        Source::Gitea => {
+            if args.api_url.is_none() {
+                bail!("--source gitea requires --api-url, e.g. https://codeberg.org/api/v1");
+            }
 
-        for (k, v) in map {
-            if to_markdown {
-                println!("- [{}]({})", k, v);
-            } else {
-                println!("{k}: {v}");
+            let source = GiteaSource::new(
+                token,
+                GiteaOptions {
+                    orgs: args.org.clone(),
+                    repos: args.repo.clone(),
+                    api_url: api_url(&args, ""),
+                    all_branches: args.all_branches,
+                    mode,
+                    cache_dir: cache::default_dir()?,
+                },
+                pb.clone(),
+            )?;
+
+            collect(&source, &args, window, mode, pb.as_ref()).await?
+        }
+        Source::Local => {
+            if args.repos_dir.is_none() {
+                bail!("--source local requires --repos-dir");
             }
+
+            // Every clone of `--repos-dir` belongs to the one organization.
+            let [org] = args.org.as_slice() else {
+                bail!("--source local requires exactly one --org");
+            };
+            if !args.repo.is_empty() {
+                bail!("--repo is not supported by --source local, clone it into --repos-dir");
+            }
+
+            let source = LocalSource::new(LocalOptions {
+                org: org.clone(),
+                dir: args.repos_dir.clone().unwrap_or_default(),
+                refs: args.refs.clone(),
+                all_branches: args.all_branches,
+            });
+
+            collect(&source, &args, window, mode, pb.as_ref()).await?
         }
+    };
+
+    if let Some(pb) = pb {
+        pb.finish_and_clear();
     }
 
+    output::print_report(format, &report)?;
+
     Ok(())
 }
 
-async fn get_repos(client: &Client, token: &str, org: &str) -> Result<Vec<Repo>> {
-    Ok(client
-        .get(format!(
-            "https://api.github.com/orgs/{org}/repos?per_page=100&sort=pushed"
-        ))
-        .header("Authorization", format!("Bearer {}", token))
-        .send()
-        .await?
-        .error_for_status()?
-        .json::<Vec<Repo>>()
-        .await?)
+/// `--api-url` without a trailing slash, or `default`.
+fn api_url(args: &Args, default: &str) -> String {
+    args.api_url
+        .as_deref()
+        .unwrap_or(default)
+        .trim_end_matches('/')
+        .to_string()
 }
 
-async fn get_commits(
-    client: &Client,
-    token: &str,
-    repo_api_url: &str,
-    page: u64,
-) -> std::result::Result<Vec<Commit>, reqwest::Error> {
-    client
-        .get(format!(
-            "{}/commits?page={}&per_page=100",
-            repo_api_url, page
-        ))
-        .header("Authorization", format!("Bearer {}", token))
-        .send()
+/// Count the contributions `source` knows about into a report.
+async fn collect<S: ContributionSource>(
+    source: &S,
+    args: &Args,
+    window: Window,
+    mode: CacheMode,
+    pb: Option<&ProgressBar>,
+) -> Result<Report> {
+    let filter = RepoFilter::new(
+        &args.include_repo,
+        &args.exclude_repo,
+        args.topic.clone(),
+        args.no_forks,
+        args.no_archived,
+        args.no_templates,
+    )?;
+
+    let identities = match &args.mailmap {
+        Some(path) => Identities::load(path)?,
+        None => Identities::default(),
+    };
+    let mut contributors = Contributors::new(identities, BotFilter::new(&args.bot)?);
+
+    update_pb(pb, "Getting matches repos ...".to_string());
+    let repos = source
+        .list_repos(window)
         .await?
-        .error_for_status()?
-        .json::<Vec<Commit>>()
-        .await
-}
+        .into_iter()
+        .filter(|repo| {
+            // Only names are known offline.
+            if mode == CacheMode::Offline {
+                filter.matches_name(&repo.full_name)
+            } else {
+                filter.matches(repo)
+            }
+        })
+        .collect::<Vec<_>>();
 
-async fn is_org_user<'a>(
-    client: &'a Client,
-    user: &'a str,
-    token: &'a str,
-    pb: Option<&ProgressBar>,
-) -> Result<(&'a str, bool)> {
-    update_pb(pb, format!("Checking {user} is org user ..."));
-
-    let resp = client
-        .get(format!(
-            "https://api.github.com/orgs/aosc-dev/memberships/{}",
-            user
-        ))
-        .header("Authorization", format!("Bearer {}", token))
-        .send()
-        .await
-        .and_then(|x| x.error_for_status());
-
-    match resp {
-        Ok(_) => Ok((user, true)),
-        Err(e) => match e.status() {
-            Some(StatusCode::NOT_FOUND) => Ok((user, false)),
-            _ => bail!("Network is not reachable: {e}"),
+    if let Some(pb) = pb {
+        pb.println(format!(
+            "A total of {} repos have been modified between {} and {}.",
+            repos.len(),
+            window.since,
+            window.until
+        ));
+    } else {
+        info!(
+            "A total of {} repos have been modified between {} and {}.",
+            repos.len(),
+            window.since,
+            window.until
+        );
+    }
+
+    debug!("Repos: {:?}", repos);
+
+    let options = CommitOptions {
+        trailers: args.trailers.clone(),
+        lines: if args.lines {
+            Some(LineOptions {
+                paths: PathFilter::new(&args.exclude_path)?,
+                cap: args.max_lines_per_commit,
+            })
+        } else {
+            None
         },
+    };
+    source::count_commits(
+        source,
+        &repos,
+        window,
+        args.thread,
+        &options,
+        &mut contributors,
+    )
+    .await;
+
+    if args.pulls {
+        source::count_pulls(source, &repos, window, args.thread, &mut contributors).await;
+    }
+
+    if args.issues {
+        source::count_issues(source, &repos, window, args.thread, &mut contributors).await;
     }
+
+    let mut report = contributors.into_report(&args.org, &args.repo, window, args.sort_by);
+    report.apply_membership(&source.membership().await?, args.filter_org_user);
+
+    if args.teams || args.team.is_some() || args.team_repos {
+        update_pb(pb, "Getting teams ...".to_string());
+        let teams = source.teams(args.team_repos).await?;
+        report.apply_teams(&teams, args.team.as_deref())?;
+    }
+
+    Ok(report)
 }
 
-async fn get_commits_info_by_url(
-    client: &Client,
-    url: String,
-    token: &str,
-    days_duration: ChronoDuration,
-    pb: Option<&ProgressBar>,
-) -> Result<Vec<Commit>> {
-    let mut page = 1;
-    let mut filter_author = vec![];
-
-    loop {
-        update_pb(pb, format!("Getting repo: {} page: {}", url, page));
-
-        let json = match get_commits(&client, &token, &url, page).await {
-            Ok(json) => json,
-            Err(e) => match e.status() {
-                Some(StatusCode::CONFLICT) => {
-                    bail!("Git Repository is empty: {}", e)
-                }
-                _ => bail!("Failed to get commits {}: {e}", url),
-            },
-        };
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    const CONFIG: &str = r#"
+[defaults]
+org = "aosc-dev"
+thread = 8
+days = 31
+pulls = true
+
+[profiles.quarterly]
+since = "2026-04-01"
+until = "2026-06-30"
+lines = true
+"#;
+
+    fn args(cli: &[&str], profile: Option<&str>) -> Args {
+        let matches = negatable_flags(Args::command())
+            .try_get_matches_from(["kpi"].iter().chain(cli))
+            .unwrap();
+        let mut args = Args::from_arg_matches(&matches).unwrap();
+        let config: Config = toml::from_str(CONFIG).unwrap();
+        args.apply(config.profile(profile).unwrap(), &matches)
+            .unwrap();
+        args
+    }
 
-        if json.is_empty() {
-            return Ok(filter_author);
-        }
+    #[test]
+    fn prefers_the_command_line() {
+        let args = args(&["--thread", "2", "--org", "aosc-tw"], None);
 
-        for i in json {
-            if let Some(commit) = &i.commit {
-                let committer_date = &commit.committer.date;
-                let author_date = &commit.author.date;
-                let committer_dt = DateTime::parse_from_rfc3339(committer_date)?.to_utc();
-                let author_dt = DateTime::parse_from_rfc3339(author_date)?.to_utc();
-                if Utc::now() - committer_dt > days_duration
-                    && Utc::now() - author_dt > days_duration
-                {
-                    return Ok(filter_author);
-                }
-
-                filter_author.push(i);
-            }
-        }
+        assert_eq!(args.thread, 2);
+        assert_eq!(args.org, ["aosc-tw"]);
+        assert_eq!(args.days, Some(31));
+    }
+
+    #[test]
+    fn turns_off_flags_of_the_file() {
+        assert!(args(&[], None).pulls);
+        assert!(!args(&["--pulls=false"], None).pulls);
+        assert!(args(&["--pulls=true"], None).pulls);
 
-        page += 1;
+        // A bare flag does not take the next argument as its value.
+        let args = args(&["--lines", "--org", "aosc-tw"], None);
+        assert!(args.lines);
+        assert_eq!(args.org, ["aosc-tw"]);
     }
-}
 
-fn update_pb(pb: Option<&ProgressBar>, msg: String) {
-    if let Some(pb) = pb {
-        pb.set_message(msg);
-    } else {
-        info!("{}", msg);
+    #[test]
+    fn keeps_the_window_whole() {
+        let quarterly = args(&[], Some("quarterly"));
+        assert_eq!(quarterly.days, None);
+        assert_eq!(
+            quarterly.since,
+            Some(Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(
+            quarterly.until,
+            Some(Utc.with_ymd_and_hms(2026, 6, 30, 23, 59, 59).unwrap())
+        );
+        assert!(quarterly.lines && quarterly.pulls);
+
+        let since = args(&["--since", "2026-05-01"], None);
+        assert_eq!(since.days, None);
+
+        let days = args(&["--days", "7"], Some("quarterly"));
+        assert_eq!(days.days, Some(7));
+        assert_eq!(days.since, None);
     }
 }
@@ -0,0 +1,290 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use eyre::Result;
+use serde::Serialize;
+
+use crate::stats::{ContributorStats, Membership, Report, TeamReport, Totals};
+
+/// Bumped whenever a field of the JSON or CSV output is renamed, removed or changes meaning.
+pub const SCHEMA_VERSION: u32 = 5;
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default)]
+pub enum Format {
+    /// `login: url` lines with statistics
+    #[default]
+    Plain,
+    /// Markdown bullet list
+    Markdown,
+    /// A single JSON document
+    Json,
+    /// One CSV row per contributor
+    Csv,
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    schema_version: u32,
+    #[serde(flatten)]
+    report: &'a Report,
+}
+
+#[derive(Serialize)]
+struct CsvRecord<'a> {
+    login: &'a str,
+    html_url: &'a str,
+    authored: u64,
+    committed: u64,
+    additions: u64,
+    deletions: u64,
+    files_changed: u64,
+    co_authored: u64,
+    pulls_opened: u64,
+    pulls_merged: u64,
+    pulls_closed: u64,
+    pulls_reviewed: u64,
+    issues_opened: u64,
+    issues_closed: u64,
+    comments: u64,
+    repos: String,
+    orgs: String,
+    first_commit: Option<String>,
+    last_commit: Option<String>,
+    membership: Membership,
+    automation: bool,
+    teams: String,
+}
+
+pub fn print_report(format: Format, report: &Report) -> Result<()> {
+    let contributors = &report.contributors;
+    let mut stdout = io::stdout().lock();
+
+    match format {
+        Format::Plain | Format::Markdown => {
+            let to_markdown = matches!(format, Format::Markdown);
+            for i in contributors {
+                print_contributor(&mut stdout, i, to_markdown)?;
+            }
+
+            if !report.automation.is_empty() {
+                if to_markdown {
+                    writeln!(stdout, "\n### Automation\n")?;
+                } else {
+                    writeln!(stdout, "\nAutomation:")?;
+                }
+
+                for i in &report.automation {
+                    print_contributor(&mut stdout, i, to_markdown)?;
+                }
+            }
+
+            let by_login = contributors
+                .iter()
+                .map(|i| (i.login.as_str(), i))
+                .collect::<HashMap<_, _>>();
+            for team in &report.teams {
+                print_team(&mut stdout, team, &by_login, to_markdown)?;
+            }
+        }
+        Format::Json => {
+            let report = JsonReport {
+                schema_version: SCHEMA_VERSION,
+                report,
+            };
+
+            serde_json::to_writer_pretty(&mut stdout, &report)?;
+            writeln!(stdout)?;
+        }
+        Format::Csv => {
+            let mut wtr = csv::Writer::from_writer(stdout);
+
+            let mut teams = HashMap::<&str, Vec<String>>::new();
+            for team in &report.teams {
+                for login in &team.contributors {
+                    teams
+                        .entry(login)
+                        .or_default()
+                        .push(format!("{}/{}", team.org, team.slug));
+                }
+            }
+
+            for i in contributors.iter().chain(&report.automation) {
+                wtr.serialize(CsvRecord {
+                    login: &i.login,
+                    html_url: &i.html_url,
+                    authored: i.authored,
+                    committed: i.committed,
+                    additions: i.additions,
+                    deletions: i.deletions,
+                    files_changed: i.files_changed,
+                    co_authored: i.co_authored,
+                    pulls_opened: i.pulls_opened,
+                    pulls_merged: i.pulls_merged,
+                    pulls_closed: i.pulls_closed,
+                    pulls_reviewed: i.pulls_reviewed,
+                    issues_opened: i.issues_opened,
+                    issues_closed: i.issues_closed,
+                    comments: i.comments,
+                    repos: i.repos.iter().cloned().collect::<Vec<_>>().join(";"),
+                    orgs: i.orgs.iter().cloned().collect::<Vec<_>>().join(";"),
+                    first_commit: i.first_commit.map(|d| d.to_rfc3339()),
+                    last_commit: i.last_commit.map(|d| d.to_rfc3339()),
+                    membership: i.membership,
+                    automation: i.bot,
+                    teams: teams
+                        .get(i.login.as_str())
+                        .map(|t| t.join(";"))
+                        .unwrap_or_default(),
+                })?;
+            }
+
+            wtr.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn print_contributor(
+    w: &mut impl Write,
+    stats: &ContributorStats,
+    to_markdown: bool,
+) -> Result<()> {
+    let ContributorStats {
+        login,
+        html_url,
+        repos,
+        first_commit,
+        last_commit,
+        membership,
+        ..
+    } = stats;
+
+    let format_date = |d: &Option<DateTime<Utc>>| {
+        d.map_or_else(|| "-".to_string(), |d| d.format("%Y-%m-%d").to_string())
+    };
+    let first = format_date(first_commit);
+    let last = format_date(last_commit);
+    let repos = repos.len();
+    let activity = activity(&Totals::from(stats));
+
+    if to_markdown {
+        let activity = activity
+            .iter()
+            .map(|(n, _, label)| format!("{n} {label}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        writeln!(
+            w,
+            "- [{login}]({html_url}) ({membership}): {activity} in {repos} repos ({first} ~ {last})"
+        )?;
+    } else {
+        let activity = activity
+            .iter()
+            .map(|(n, key, _)| format!("{key}: {n}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        writeln!(
+            w,
+            "{login}: {html_url} membership: {membership} {activity} repos: {repos} first: {first} last: {last}"
+        )?;
+    }
+
+    Ok(())
+}
+
+/// A heading with the repositories of the team, its contributors and their totals.
+fn print_team(
+    w: &mut impl Write,
+    team: &TeamReport,
+    by_login: &HashMap<&str, &ContributorStats>,
+    to_markdown: bool,
+) -> Result<()> {
+    let TeamReport {
+        org,
+        slug,
+        name,
+        contributors,
+        totals,
+        repos,
+    } = team;
+
+    let repos = repos
+        .iter()
+        .map(|r| format!("{} ({})", r.full_name, r.permission))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let activity = activity(totals);
+
+    if to_markdown {
+        writeln!(w, "\n### {name} ({org}/{slug})\n")?;
+        if !repos.is_empty() {
+            writeln!(w, "Repositories: {repos}\n")?;
+        }
+    } else {
+        writeln!(w, "\nTeam {org}/{slug} ({name}):")?;
+        if !repos.is_empty() {
+            writeln!(w, "repos: {repos}")?;
+        }
+    }
+
+    for login in contributors {
+        if let Some(stats) = by_login.get(login.as_str()) {
+            print_contributor(w, stats, to_markdown)?;
+        }
+    }
+
+    let contributors = totals.contributors;
+    if to_markdown {
+        let activity = activity
+            .iter()
+            .map(|(n, _, label)| format!("{n} {label}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        writeln!(w, "\nTotal: {contributors} contributors, {activity}")?;
+    } else {
+        let activity = activity
+            .iter()
+            .map(|(n, key, _)| format!("{key}: {n}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        writeln!(w, "total: contributors: {contributors} {activity}")?;
+    }
+
+    Ok(())
+}
+
+/// Counters to print as `(count, plain key, markdown label)`, commit counters
+/// are always shown, the others only when non-zero.
+fn activity(stats: &Totals) -> Vec<(u64, &'static str, &'static str)> {
+    let mut res = vec![
+        (stats.authored, "authored", "authored"),
+        (stats.committed, "committed", "committed"),
+    ];
+
+    let optional = [
+        (stats.additions, "additions", "lines added"),
+        (stats.deletions, "deletions", "lines deleted"),
+        (stats.files_changed, "files_changed", "files changed"),
+        (stats.co_authored, "co_authored", "co-authored"),
+        (stats.pulls_opened, "pulls_opened", "PRs opened"),
+        (stats.pulls_merged, "pulls_merged", "PRs merged"),
+        (stats.pulls_closed, "pulls_closed", "PRs closed"),
+        (stats.pulls_reviewed, "pulls_reviewed", "PRs reviewed"),
+        (stats.issues_opened, "issues_opened", "issues opened"),
+        (stats.issues_closed, "issues_closed", "issues closed"),
+        (stats.comments, "comments", "comments"),
+    ];
+
+    res.extend(optional.into_iter().filter(|(n, _, _)| *n > 0));
+
+    res
+}
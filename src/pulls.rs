@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use eyre::Result;
+use indicatif::ProgressBar;
+use serde::Deserialize;
+
+use crate::{client::ApiClient, Author, Window};
+
+#[derive(Deserialize, Debug)]
+pub struct PullRequest {
+    pub number: u64,
+    pub user: Option<Author>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub merged_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Review {
+    pub user: Option<Author>,
+    /// `None` for pending reviews
+    pub submitted_at: Option<DateTime<Utc>>,
+}
+
+/// A pull request with its reviews.
+#[derive(Debug)]
+pub struct PullActivity {
+    pub pull: PullRequest,
+    pub reviews: Vec<Review>,
+}
+
+/// Collect the pull requests of a repository updated inside `window`, with their reviews.
+pub async fn get_pulls(
+    gh: &ApiClient,
+    repo_api_url: &str,
+    window: Window,
+    pb: Option<&ProgressBar>,
+) -> Result<Vec<PullActivity>> {
+    // Sorted by update time, any activity inside the window updates the pull request.
+    let pulls = gh
+        .get_paginated(
+            format!(
+                "{}/pulls?state=all&sort=updated&direction=desc&per_page=100",
+                repo_api_url
+            ),
+            pb,
+            |pull: &PullRequest| Ok(pull.updated_at >= window.since),
+        )
+        .await?;
+
+    let mut res = vec![];
+
+    for pull in pulls {
+        let reviews = gh
+            .get_paginated(
+                format!(
+                    "{}/pulls/{}/reviews?per_page=100",
+                    repo_api_url, pull.number
+                ),
+                pb,
+                |_| Ok(true),
+            )
+            .await?;
+
+        res.push(PullActivity { pull, reviews });
+    }
+
+    Ok(res)
+}
@@ -0,0 +1,331 @@
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+};
+
+use eyre::{bail, Result};
+use futures::StreamExt;
+use tracing::{debug, error};
+
+use crate::{
+    filter::PathFilter,
+    issues::IssueActivity,
+    pulls::PullActivity,
+    teams::Team,
+    trailer::{self, Trailer},
+    Author, Commit, Contributors, FileChange, Membership, Repo, Window,
+};
+
+/// Where repositories, commits and members of an organization come from.
+pub trait ContributionSource {
+    /// Repositories of the organization which may have commits inside `window`.
+    fn list_repos(&self, window: Window) -> impl Future<Output = Result<Vec<Repo>>>;
+
+    /// Commits of `repo` inside `window`.
+    fn list_commits(
+        &self,
+        repo: &Repo,
+        window: Window,
+    ) -> impl Future<Output = Result<Vec<Commit>>>;
+
+    /// Membership of the users known to the organization, anyone else is external.
+    fn membership(&self) -> impl Future<Output = Result<HashMap<String, Membership>>>;
+
+    /// Pull requests of `repo` updated inside `window`, with their reviews.
+    fn list_pulls(
+        &self,
+        _repo: &Repo,
+        _window: Window,
+    ) -> impl Future<Output = Result<Vec<PullActivity>>> {
+        async { bail!("Pull requests are not supported by this source") }
+    }
+
+    /// Issues and comments of `repo` updated inside `window`.
+    fn list_issues(
+        &self,
+        _repo: &Repo,
+        _window: Window,
+    ) -> impl Future<Output = Result<IssueActivity>> {
+        async { bail!("Issues are not supported by this source") }
+    }
+
+    /// Lines changed per file by the commit `sha` of `repo`.
+    fn commit_files(
+        &self,
+        _repo: &Repo,
+        _sha: &str,
+    ) -> impl Future<Output = Result<Vec<FileChange>>> {
+        async { bail!("Line statistics are not supported by this source") }
+    }
+
+    /// Teams of the organization with their members, and their repositories
+    /// if `with_repos` is set.
+    fn teams(&self, _with_repos: bool) -> impl Future<Output = Result<Vec<Team>>> {
+        async { bail!("Teams are not supported by this source") }
+    }
+
+    /// The account with the public email `email`, if the source can search for it.
+    fn find_user(&self, _email: &str) -> impl Future<Output = Result<Option<Author>>> {
+        async { Ok(None) }
+    }
+
+    /// Commits of every repo in `repos` paired with its full name, fetching `thread`
+    /// repos at a time. Sources which can batch requests override this.
+    fn list_all_commits(
+        &self,
+        repos: &[Repo],
+        window: Window,
+        thread: usize,
+    ) -> impl Future<Output = Vec<Result<(String, Vec<Commit>)>>> {
+        list_commits_concurrently(self, repos, window, thread)
+    }
+}
+
+/// Drop repeated repos, e.g. listed by `--repo` and by their org, keeping the
+/// first. Names are compared case-insensitively like forges do.
+pub fn dedup_repos(repos: Vec<Repo>) -> Vec<Repo> {
+    let mut seen = HashSet::new();
+    repos
+        .into_iter()
+        .filter(|repo| seen.insert(repo.full_name.to_lowercase()))
+        .collect()
+}
+
+/// The default [`ContributionSource::list_all_commits`], one repo after another
+/// with up to `thread` in flight.
+pub async fn list_commits_concurrently<S: ContributionSource + ?Sized>(
+    source: &S,
+    repos: &[Repo],
+    window: Window,
+    thread: usize,
+) -> Vec<Result<(String, Vec<Commit>)>> {
+    let mut tasks = vec![];
+
+    for repo in repos {
+        tasks.push(async move {
+            let commits = source.list_commits(repo, window).await?;
+            Ok((repo.full_name.clone(), commits))
+        });
+    }
+
+    futures::stream::iter(tasks)
+        .buffer_unordered(thread)
+        .collect::<Vec<_>>()
+        .await
+}
+
+/// What else to count about each commit.
+#[derive(Default)]
+pub struct CommitOptions {
+    /// Also credit everyone named in these trailers
+    pub trailers: Vec<Trailer>,
+    /// Also count the lines changed by each commit, with one more request per commit
+    pub lines: Option<LineOptions>,
+}
+
+/// How the lines changed by a commit are counted.
+pub struct LineOptions {
+    /// Files whose changes are ignored, e.g. lockfiles, vendored trees and generated files
+    pub paths: PathFilter,
+    /// Count at most this many added, and as many deleted lines per commit
+    pub cap: Option<u64>,
+}
+
+/// Count the commits of `repos` into `contributors`, repos which fail are
+/// logged and skipped.
+pub async fn count_commits<S: ContributionSource>(
+    source: &S,
+    repos: &[Repo],
+    window: Window,
+    thread: usize,
+    options: &CommitOptions,
+    contributors: &mut Contributors,
+) {
+    let mut fetched = vec![];
+
+    for i in source.list_all_commits(repos, window, thread).await {
+        match i {
+            Ok((repo, commits)) => {
+                for commit in &commits {
+                    contributors.add_commit(&repo, commit);
+                }
+
+                fetched.push((repo, commits));
+            }
+            Err(e) => {
+                error!("{:?}", e);
+            }
+        }
+    }
+
+    if !options.trailers.is_empty() {
+        count_trailers(source, &fetched, &options.trailers, contributors).await;
+    }
+
+    if let Some(lines) = &options.lines {
+        count_lines(source, repos, &fetched, thread, lines, contributors).await;
+    }
+}
+
+/// Credit everyone named in one of `trailers` of a commit, their emails are
+/// resolved with the identities of `contributors`, the accounts linked to other
+/// commits, or [`ContributionSource::find_user`].
+async fn count_trailers<S: ContributionSource>(
+    source: &S,
+    fetched: &[(String, Vec<Commit>)],
+    trailers: &[Trailer],
+    contributors: &mut Contributors,
+) {
+    // Lowercase emails to the accounts they belong to, `None` if unknown.
+    let mut users = HashMap::new();
+    for commit in fetched.iter().flat_map(|(_, commits)| commits) {
+        let Some(info) = &commit.commit else {
+            continue;
+        };
+
+        for (user, identity) in [
+            (&commit.author, &info.author),
+            (&commit.committer, &info.committer),
+        ] {
+            if let Some(user) = user {
+                users.insert(identity.email.to_lowercase(), Some(user.clone()));
+            }
+        }
+    }
+
+    for (repo, commits) in fetched {
+        for commit in commits {
+            let Some(info) = &commit.commit else {
+                continue;
+            };
+
+            for email in trailer::parse(&info.message, trailers) {
+                let key = email.to_lowercase();
+                if !users.contains_key(&key)
+                    && contributors.identities().resolve_email(&email).is_none()
+                {
+                    let user = match source.find_user(&email).await {
+                        Ok(user) => user,
+                        Err(e) => {
+                            debug!("Failed to find the user of {email}: {e}");
+                            None
+                        }
+                    };
+
+                    users.insert(key.clone(), user);
+                }
+
+                let user = users.get(&key).cloned().flatten();
+                contributors.add_co_author(repo, commit, user.as_ref(), &email);
+            }
+        }
+    }
+}
+
+/// Credit the authors of `fetched` commits with the lines they changed.
+async fn count_lines<S: ContributionSource>(
+    source: &S,
+    repos: &[Repo],
+    fetched: &[(String, Vec<Commit>)],
+    thread: usize,
+    options: &LineOptions,
+    contributors: &mut Contributors,
+) {
+    let repos = repos
+        .iter()
+        .map(|r| (r.full_name.as_str(), r))
+        .collect::<HashMap<_, _>>();
+    let mut tasks = vec![];
+
+    for (repo, commits) in fetched {
+        let Some(repo) = repos.get(repo.as_str()).copied() else {
+            continue;
+        };
+
+        for commit in commits {
+            tasks.push(async move { (commit, source.commit_files(repo, &commit.sha).await) });
+        }
+    }
+
+    let stream = futures::stream::iter(tasks)
+        .buffer_unordered(thread)
+        .collect::<Vec<_>>()
+        .await;
+
+    for (commit, i) in stream {
+        match i {
+            Ok(files) => {
+                contributors.add_lines(commit, &files, &options.paths, options.cap);
+            }
+            Err(e) => {
+                error!("Failed to get the changes of {}: {:?}", commit.sha, e);
+            }
+        }
+    }
+}
+
+/// Count the pull requests of `repos` into `contributors`, repos which fail are
+/// logged and skipped.
+pub async fn count_pulls<S: ContributionSource>(
+    source: &S,
+    repos: &[Repo],
+    window: Window,
+    thread: usize,
+    contributors: &mut Contributors,
+) {
+    let mut tasks = vec![];
+
+    for repo in repos {
+        tasks.push(async move { (repo, source.list_pulls(repo, window).await) });
+    }
+
+    let stream = futures::stream::iter(tasks)
+        .buffer_unordered(thread)
+        .collect::<Vec<_>>()
+        .await;
+
+    for (repo, i) in stream {
+        match i {
+            Ok(pulls) => {
+                for pull in pulls {
+                    contributors.add_pull(&repo.full_name, &pull, window);
+                }
+            }
+            Err(e) => {
+                error!("Failed to get pull requests of {}: {:?}", repo.full_name, e);
+            }
+        }
+    }
+}
+
+/// Count the issues and comments of `repos` into `contributors`, repos which
+/// fail are logged and skipped.
+pub async fn count_issues<S: ContributionSource>(
+    source: &S,
+    repos: &[Repo],
+    window: Window,
+    thread: usize,
+    contributors: &mut Contributors,
+) {
+    let mut tasks = vec![];
+
+    for repo in repos {
+        tasks.push(async move { (repo, source.list_issues(repo, window).await) });
+    }
+
+    let stream = futures::stream::iter(tasks)
+        .buffer_unordered(thread)
+        .collect::<Vec<_>>()
+        .await;
+
+    for (repo, i) in stream {
+        match i {
+            Ok(activity) => {
+                contributors.add_issues(&repo.full_name, &activity, window);
+            }
+            Err(e) => {
+                error!("Failed to get issues of {}: {:?}", repo.full_name, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,759 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Display,
+};
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use eyre::{bail, Result};
+use serde::Serialize;
+
+use crate::{
+    filter::{BotFilter, PathFilter},
+    identity::Identities,
+    issues::IssueActivity,
+    pulls::PullActivity,
+    teams::{Team, TeamRepo},
+    Author, Commit, FileChange, Window,
+};
+
+/// Activity of a single contributor inside the query interval.
+#[derive(Debug, Clone, Serialize)]
+pub struct ContributorStats {
+    pub login: String,
+    pub html_url: String,
+    /// Commits where this user is the author
+    pub authored: u64,
+    /// Commits where this user is the committer
+    pub committed: u64,
+    /// Lines added by commits this user authored, only counted with line statistics
+    pub additions: u64,
+    /// Lines deleted by commits this user authored
+    pub deletions: u64,
+    /// Files changed by commits this user authored, summed per commit
+    pub files_changed: u64,
+    /// Commits of others crediting this user in a counted trailer, e.g. `Co-authored-by`
+    pub co_authored: u64,
+    /// Pull requests opened by this user
+    pub pulls_opened: u64,
+    /// Pull requests by this user which got merged
+    pub pulls_merged: u64,
+    /// Pull requests by this user which got closed without merging
+    pub pulls_closed: u64,
+    /// Pull requests of others this user reviewed
+    pub pulls_reviewed: u64,
+    /// Issues opened by this user
+    pub issues_opened: u64,
+    /// Issues closed by this user
+    pub issues_closed: u64,
+    /// Comments on issues and pull requests
+    pub comments: u64,
+    /// Repositories (`owner/name`) this user touched
+    pub repos: BTreeSet<String>,
+    /// Owners of `repos`, i.e. the organizations, groups or users they belong to
+    pub orgs: BTreeSet<String>,
+    /// `None` if this user has no commit in the interval. Commits are dated by
+    /// their committer date, which the interval is filtered on
+    pub first_commit: Option<DateTime<Utc>>,
+    pub last_commit: Option<DateTime<Utc>>,
+    pub membership: Membership,
+    /// Whether this is an automation account, reported apart from people
+    #[serde(skip)]
+    pub bot: bool,
+}
+
+impl ContributorStats {
+    fn new(login: &str, html_url: &str) -> Self {
+        Self {
+            login: login.to_string(),
+            html_url: html_url.to_string(),
+            authored: 0,
+            committed: 0,
+            additions: 0,
+            deletions: 0,
+            files_changed: 0,
+            co_authored: 0,
+            pulls_opened: 0,
+            pulls_merged: 0,
+            pulls_closed: 0,
+            pulls_reviewed: 0,
+            issues_opened: 0,
+            issues_closed: 0,
+            comments: 0,
+            repos: BTreeSet::new(),
+            orgs: BTreeSet::new(),
+            first_commit: None,
+            last_commit: None,
+            membership: Membership::External,
+            bot: false,
+        }
+    }
+
+    fn touch(&mut self, repo: &str) {
+        if !self.repos.contains(repo) {
+            self.repos.insert(repo.to_string());
+
+            // GitLab projects may be nested in subgroups, the owner is all but the name.
+            if let Some((owner, _)) = repo.rsplit_once('/') {
+                self.orgs.insert(owner.to_string());
+            }
+        }
+    }
+
+    fn touch_commit(&mut self, repo: &str, date: DateTime<Utc>) {
+        self.touch(repo);
+        self.first_commit = Some(self.first_commit.map_or(date, |d| d.min(date)));
+        self.last_commit = Some(self.last_commit.map_or(date, |d| d.max(date)));
+    }
+}
+
+/// Sums of the counters of several contributors.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Totals {
+    pub contributors: u64,
+    pub authored: u64,
+    pub committed: u64,
+    pub additions: u64,
+    pub deletions: u64,
+    pub files_changed: u64,
+    pub co_authored: u64,
+    pub pulls_opened: u64,
+    pub pulls_merged: u64,
+    pub pulls_closed: u64,
+    pub pulls_reviewed: u64,
+    pub issues_opened: u64,
+    pub issues_closed: u64,
+    pub comments: u64,
+}
+
+impl Totals {
+    pub fn add(&mut self, stats: &ContributorStats) {
+        self.contributors += 1;
+        self.authored += stats.authored;
+        self.committed += stats.committed;
+        self.additions += stats.additions;
+        self.deletions += stats.deletions;
+        self.files_changed += stats.files_changed;
+        self.co_authored += stats.co_authored;
+        self.pulls_opened += stats.pulls_opened;
+        self.pulls_merged += stats.pulls_merged;
+        self.pulls_closed += stats.pulls_closed;
+        self.pulls_reviewed += stats.pulls_reviewed;
+        self.issues_opened += stats.issues_opened;
+        self.issues_closed += stats.issues_closed;
+        self.comments += stats.comments;
+    }
+}
+
+impl From<&ContributorStats> for Totals {
+    fn from(stats: &ContributorStats) -> Self {
+        let mut res = Self::default();
+        res.add(stats);
+        res
+    }
+}
+
+/// How a contributor relates to the organization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Membership {
+    Member,
+    OutsideCollaborator,
+    External,
+}
+
+impl Display for Membership {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Membership::Member => write!(f, "member"),
+            Membership::OutsideCollaborator => write!(f, "outside collaborator"),
+            Membership::External => write!(f, "external"),
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default)]
+pub enum SortBy {
+    #[default]
+    Login,
+    Authored,
+    Committed,
+    Additions,
+    Deletions,
+    FilesChanged,
+    CoAuthored,
+    PullsOpened,
+    PullsMerged,
+    PullsClosed,
+    PullsReviewed,
+    IssuesOpened,
+    IssuesClosed,
+    Comments,
+    Repos,
+    First,
+    Last,
+}
+
+/// The sorted result of a run.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// Organizations given with `--org`
+    pub orgs: Vec<String>,
+    /// Repositories given with `--repo`
+    pub repos: Vec<String>,
+    #[serde(flatten)]
+    pub window: Window,
+    pub contributors: Vec<ContributorStats>,
+    /// Activity of bots and other automation accounts, kept out of `contributors`
+    pub automation: Vec<ContributorStats>,
+    /// Empty unless broken down by team
+    pub teams: Vec<TeamReport>,
+}
+
+/// The contributors who are members of a team.
+#[derive(Debug, Serialize)]
+pub struct TeamReport {
+    pub org: String,
+    pub slug: String,
+    pub name: String,
+    /// Logins of the members in `contributors` of the report, in the same order
+    pub contributors: Vec<String>,
+    pub totals: Totals,
+    /// Empty unless the repositories of the team were asked for
+    pub repos: Vec<TeamRepo>,
+}
+
+impl Report {
+    /// Classify every contributor with `membership`, anyone not listed is external.
+    /// Only members are kept if `members_only` is set, automation is always kept.
+    pub fn apply_membership(
+        &mut self,
+        membership: &HashMap<String, Membership>,
+        members_only: bool,
+    ) {
+        for i in self.contributors.iter_mut().chain(&mut self.automation) {
+            i.membership = membership
+                .get(&i.login)
+                .copied()
+                .unwrap_or(Membership::External);
+        }
+
+        if members_only {
+            self.contributors
+                .retain(|i| i.membership == Membership::Member);
+        }
+    }
+
+    /// Break the contributors down by `teams`, members of several teams are
+    /// listed in each. If `only` names a team (`slug` or `org/slug`), just that
+    /// team is reported and contributors outside of it are dropped.
+    pub fn apply_teams(&mut self, teams: &[Team], only: Option<&str>) -> Result<()> {
+        let teams = match only {
+            Some(name) => {
+                let teams = teams
+                    .iter()
+                    .filter(|t| t.is_named(name))
+                    .collect::<Vec<_>>();
+                if teams.is_empty() {
+                    bail!("No team {name} in {}", self.orgs.join(", "));
+                }
+
+                let members = teams
+                    .iter()
+                    .flat_map(|t| &t.members)
+                    .collect::<HashSet<_>>();
+                self.contributors.retain(|i| members.contains(&i.login));
+
+                teams
+            }
+            None => teams.iter().collect(),
+        };
+
+        self.teams = teams
+            .into_iter()
+            .map(|team| {
+                let members = team.members.iter().collect::<HashSet<_>>();
+                let mut totals = Totals::default();
+                let contributors = self
+                    .contributors
+                    .iter()
+                    .filter(|i| members.contains(&i.login))
+                    .map(|i| {
+                        totals.add(i);
+                        i.login.clone()
+                    })
+                    .collect();
+
+                TeamReport {
+                    org: team.org.clone(),
+                    slug: team.slug.clone(),
+                    name: team.name.clone(),
+                    contributors,
+                    totals,
+                    repos: team.repos.clone(),
+                }
+            })
+            .collect();
+
+        Ok(())
+    }
+}
+
+/// Per-login aggregation of commits, pull requests and issues.
+#[derive(Debug, Default)]
+pub struct Contributors {
+    stats: HashMap<String, ContributorStats>,
+    identities: Identities,
+    bots: BotFilter,
+}
+
+impl Contributors {
+    /// Credit everyone under their canonical login in `identities`, and tell
+    /// automation accounts apart with `bots`.
+    pub fn new(identities: Identities, bots: BotFilter) -> Self {
+        Self {
+            stats: HashMap::new(),
+            identities,
+            bots,
+        }
+    }
+
+    pub fn add_commit(&mut self, repo: &str, commit: &Commit) {
+        let Some(info) = &commit.commit else {
+            return;
+        };
+
+        // The committer date, like the interval, so rebased and cherry-picked
+        // commits cannot date back before it.
+        let date = info.committer.date;
+
+        if let Some(stats) = self.commit_entry(commit.author.as_ref(), &info.author.email) {
+            stats.authored += 1;
+            stats.touch_commit(repo, date);
+        }
+
+        if let Some(stats) = self.commit_entry(commit.committer.as_ref(), &info.committer.email) {
+            stats.committed += 1;
+            stats.touch_commit(repo, date);
+        }
+    }
+
+    /// Credit the author of `commit` with the lines it changed in `files`.
+    /// Excluded files are skipped, and each counter is capped at `cap`.
+    pub fn add_lines(
+        &mut self,
+        commit: &Commit,
+        files: &[FileChange],
+        paths: &PathFilter,
+        cap: Option<u64>,
+    ) {
+        let Some(info) = &commit.commit else {
+            return;
+        };
+
+        let files = files
+            .iter()
+            .filter(|f| paths.matches(&f.filename))
+            .collect::<Vec<_>>();
+        let cap = |n: u64| cap.map_or(n, |cap| n.min(cap));
+
+        if let Some(stats) = self.commit_entry(commit.author.as_ref(), &info.author.email) {
+            stats.additions += cap(files.iter().map(|f| f.additions).sum());
+            stats.deletions += cap(files.iter().map(|f| f.deletions).sum());
+            stats.files_changed += files.len() as u64;
+        }
+    }
+
+    /// Credit the user with `email` named in a trailer of `commit`, unless
+    /// they authored it. `user` is their account, if known.
+    pub fn add_co_author(
+        &mut self,
+        repo: &str,
+        commit: &Commit,
+        user: Option<&Author>,
+        email: &str,
+    ) {
+        let Some(info) = &commit.commit else {
+            return;
+        };
+
+        if email.eq_ignore_ascii_case(&info.author.email) {
+            return;
+        }
+
+        let login = |user: Option<&Author>| user.and_then(|u| u.login.clone());
+        let author = self
+            .identities
+            .resolve(
+                login(commit.author.as_ref()).as_deref(),
+                Some(&info.author.email),
+            )
+            .map(|s| s.to_string());
+        let co_author = self
+            .identities
+            .resolve(login(user).as_deref(), Some(email))
+            .map(|s| s.to_string());
+        if co_author.is_none() || co_author == author {
+            return;
+        }
+
+        if let Some(stats) = self.commit_entry(user, email) {
+            stats.co_authored += 1;
+            stats.touch_commit(repo, info.committer.date);
+        }
+    }
+
+    /// Credit the author for opening, merging or closing `activity.pull` inside
+    /// `window`, and each other user who reviewed it inside `window`.
+    pub fn add_pull(&mut self, repo: &str, activity: &PullActivity, window: Window) {
+        let pull = &activity.pull;
+        let author = pull.user.as_ref().and_then(|u| u.login.as_deref());
+
+        if let Some(stats) = self.entry(pull.user.as_ref()) {
+            let mut touched = false;
+
+            if window.contains(pull.created_at) {
+                stats.pulls_opened += 1;
+                touched = true;
+            }
+
+            match (pull.merged_at, pull.closed_at) {
+                (Some(merged_at), _) if window.contains(merged_at) => {
+                    stats.pulls_merged += 1;
+                    touched = true;
+                }
+                (None, Some(closed_at)) if window.contains(closed_at) => {
+                    stats.pulls_closed += 1;
+                    touched = true;
+                }
+                _ => {}
+            }
+
+            if touched {
+                stats.touch(repo);
+            }
+        }
+
+        let mut reviewers = BTreeSet::new();
+        for review in &activity.reviews {
+            let Some(login) = review.user.as_ref().and_then(|u| u.login.as_deref()) else {
+                continue;
+            };
+
+            if Some(login) == author
+                || !review.submitted_at.is_some_and(|d| window.contains(d))
+                || !reviewers.insert(login)
+            {
+                continue;
+            }
+
+            if let Some(stats) = self.entry(review.user.as_ref()) {
+                stats.pulls_reviewed += 1;
+                stats.touch(repo);
+            }
+        }
+    }
+
+    /// Credit issues opened and closed, and comments written inside `window`.
+    pub fn add_issues(&mut self, repo: &str, activity: &IssueActivity, window: Window) {
+        for issue in &activity.issues {
+            if window.contains(issue.created_at) {
+                if let Some(stats) = self.entry(issue.user.as_ref()) {
+                    stats.issues_opened += 1;
+                    stats.touch(repo);
+                }
+            }
+
+            if issue.closed_at.is_some_and(|d| window.contains(d)) {
+                if let Some(stats) = self.entry(issue.closed_by.as_ref()) {
+                    stats.issues_closed += 1;
+                    stats.touch(repo);
+                }
+            }
+        }
+
+        for comment in &activity.comments {
+            if window.contains(comment.created_at) {
+                if let Some(stats) = self.entry(comment.user.as_ref()) {
+                    stats.comments += 1;
+                    stats.touch(repo);
+                }
+            }
+        }
+    }
+
+    /// The identities contributors are resolved with.
+    pub fn identities(&self) -> &Identities {
+        &self.identities
+    }
+
+    fn entry(&mut self, user: Option<&Author>) -> Option<&mut ContributorStats> {
+        let html_url = user?.html_url.as_deref()?;
+        self.resolve(user, html_url, None)
+    }
+
+    /// Commits without a linked account are attributed by `email`, if the
+    /// identities know it.
+    fn commit_entry(
+        &mut self,
+        user: Option<&Author>,
+        email: &str,
+    ) -> Option<&mut ContributorStats> {
+        match user.and_then(|u| u.html_url.as_deref()) {
+            Some(html_url) => self.resolve(user, html_url, Some(email)),
+            None => self.resolve(user, &format!("mailto:{email}"), Some(email)),
+        }
+    }
+
+    fn resolve(
+        &mut self,
+        user: Option<&Author>,
+        html_url: &str,
+        email: Option<&str>,
+    ) -> Option<&mut ContributorStats> {
+        let login = user.and_then(|u| u.login.as_deref());
+        let canonical = self.identities.resolve(login, email)?;
+        let bot = self
+            .bots
+            .is_bot(login, email, user.and_then(|u| u.user_type.as_deref()))
+            || self.bots.is_bot(Some(canonical), None, None);
+
+        let stats = self
+            .stats
+            .entry(canonical.to_string())
+            .or_insert_with(|| ContributorStats::new(canonical, html_url));
+        stats.bot |= bot;
+
+        // Prefer the profile of the canonical login over those of its aliases
+        // and over emails.
+        if Some(canonical) == login
+            || stats.html_url.starts_with("mailto:") && !html_url.starts_with("mailto:")
+        {
+            stats.html_url = html_url.to_string();
+        }
+
+        Some(stats)
+    }
+
+    /// Numeric columns and `last` sort descending so the most active come first,
+    /// `login` and `first` sort ascending. Contributors without commits sort last
+    /// by `first` and `last`.
+    pub fn into_sorted(self, sort_by: SortBy) -> Vec<ContributorStats> {
+        let mut res = self.stats.into_values().collect::<Vec<_>>();
+
+        res.sort_by(|a, b| {
+            match sort_by {
+                SortBy::Login => a.login.cmp(&b.login),
+                SortBy::Authored => b.authored.cmp(&a.authored),
+                SortBy::Committed => b.committed.cmp(&a.committed),
+                SortBy::Additions => b.additions.cmp(&a.additions),
+                SortBy::Deletions => b.deletions.cmp(&a.deletions),
+                SortBy::FilesChanged => b.files_changed.cmp(&a.files_changed),
+                SortBy::CoAuthored => b.co_authored.cmp(&a.co_authored),
+                SortBy::PullsOpened => b.pulls_opened.cmp(&a.pulls_opened),
+                SortBy::PullsMerged => b.pulls_merged.cmp(&a.pulls_merged),
+                SortBy::PullsClosed => b.pulls_closed.cmp(&a.pulls_closed),
+                SortBy::PullsReviewed => b.pulls_reviewed.cmp(&a.pulls_reviewed),
+                SortBy::IssuesOpened => b.issues_opened.cmp(&a.issues_opened),
+                SortBy::IssuesClosed => b.issues_closed.cmp(&a.issues_closed),
+                SortBy::Comments => b.comments.cmp(&a.comments),
+                SortBy::Repos => b.repos.len().cmp(&a.repos.len()),
+                SortBy::First => match (a.first_commit, b.first_commit) {
+                    (Some(a), Some(b)) => a.cmp(&b),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                },
+                SortBy::Last => b.last_commit.cmp(&a.last_commit),
+            }
+            .then_with(|| a.login.cmp(&b.login))
+        });
+
+        res
+    }
+
+    pub fn into_report(
+        self,
+        orgs: &[String],
+        repos: &[String],
+        window: Window,
+        sort_by: SortBy,
+    ) -> Report {
+        let (automation, contributors) = self.into_sorted(sort_by).into_iter().partition(|i| i.bot);
+
+        Report {
+            orgs: orgs.to_vec(),
+            repos: repos.to_vec(),
+            window,
+            contributors,
+            automation,
+            teams: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::{RepoAuthor, RepoCommit};
+
+    fn day(d: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 6, d, 0, 0, 0).unwrap()
+    }
+
+    fn user(login: &str) -> Author {
+        Author {
+            login: Some(login.to_string()),
+            html_url: Some(format!("https://github.com/{login}")),
+            user_type: None,
+        }
+    }
+
+    fn signature(email: &str, date: DateTime<Utc>) -> RepoAuthor {
+        RepoAuthor {
+            name: String::new(),
+            email: email.to_string(),
+            date,
+        }
+    }
+
+    /// A commit authored and committed by `login`, if any, with `email`.
+    fn commit(login: Option<&str>, email: &str, d: u32) -> Commit {
+        Commit {
+            sha: format!("{email}-{d}"),
+            commit: Some(RepoCommit {
+                author: signature(email, day(d)),
+                committer: signature(email, day(d)),
+                message: String::new(),
+            }),
+            author: login.map(user),
+            committer: login.map(user),
+        }
+    }
+
+    fn get<'a>(stats: &'a [ContributorStats], login: &str) -> &'a ContributorStats {
+        stats.iter().find(|i| i.login == login).unwrap()
+    }
+
+    #[test]
+    fn counts_authored_and_committed_commits() {
+        let mut contributors = Contributors::default();
+        contributors.add_commit("org/a", &commit(Some("alice"), "alice@x", 5));
+        contributors.add_commit("org2/b", &commit(Some("alice"), "alice@x", 2));
+
+        // Authored by bob, committed by alice a week later.
+        let mut applied = commit(Some("bob"), "bob@x", 1);
+        applied.committer = Some(user("alice"));
+        if let Some(info) = &mut applied.commit {
+            info.committer = signature("alice@x", day(8));
+        }
+        contributors.add_commit("org/a", &applied);
+
+        // Nobody to credit.
+        contributors.add_commit("org/a", &commit(None, "ghost@x", 3));
+
+        let stats = contributors.into_sorted(SortBy::Login);
+        assert_eq!(stats.len(), 2);
+
+        let alice = get(&stats, "alice");
+        assert_eq!((alice.authored, alice.committed), (2, 3));
+        assert_eq!(
+            alice.repos,
+            BTreeSet::from(["org/a".into(), "org2/b".into()])
+        );
+        assert_eq!(alice.orgs, BTreeSet::from(["org".into(), "org2".into()]));
+        assert_eq!(alice.first_commit, Some(day(2)));
+        assert_eq!(alice.last_commit, Some(day(8)));
+
+        // Dated by the committer date.
+        let bob = get(&stats, "bob");
+        assert_eq!((bob.authored, bob.committed), (1, 0));
+        assert_eq!(bob.first_commit, Some(day(8)));
+    }
+
+    /// alice: 1 commit on day 5, bob: 2 on days 3 and 6, carol: 2 on days 4
+    /// and 5, dave: none.
+    fn ranked() -> Contributors {
+        let mut contributors = Contributors::default();
+        contributors.add_commit("org/a", &commit(Some("alice"), "alice@x", 5));
+        for d in [3, 6] {
+            contributors.add_commit("org/a", &commit(Some("bob"), "bob@x", d));
+        }
+        for d in [4, 5] {
+            contributors.add_commit("org/a", &commit(Some("carol"), "carol@x", d));
+        }
+        // As if they only reviewed pull requests.
+        contributors.stats.insert(
+            "dave".to_string(),
+            ContributorStats::new("dave", "https://github.com/dave"),
+        );
+
+        contributors
+    }
+
+    #[test]
+    fn sorts_by_the_selected_column() {
+        let order = |sort_by| {
+            ranked()
+                .into_sorted(sort_by)
+                .into_iter()
+                .map(|i| i.login)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(order(SortBy::Login), ["alice", "bob", "carol", "dave"]);
+        // Ties are broken by login.
+        assert_eq!(order(SortBy::Authored), ["bob", "carol", "alice", "dave"]);
+        assert_eq!(order(SortBy::First), ["bob", "carol", "alice", "dave"]);
+        assert_eq!(order(SortBy::Last), ["bob", "alice", "carol", "dave"]);
+    }
+
+    #[test]
+    fn merges_aliases_into_the_canonical_login() {
+        let identities = Identities::parse(
+            "alice <alice@work.example> @alice-old
+carol <carol@example.com>",
+        )
+        .unwrap();
+        let mut contributors = Contributors::new(identities, BotFilter::default());
+        contributors.add_commit("org/a", &commit(Some("alice-old"), "old@x", 1));
+        contributors.add_commit("org/a", &commit(None, "alice@work.example", 2));
+        contributors.add_commit("org/a", &commit(Some("alice"), "alice@x", 3));
+        // Known by email only.
+        contributors.add_commit("org/a", &commit(None, "Carol@Example.com", 4));
+
+        let stats = contributors.into_sorted(SortBy::Login);
+        assert_eq!(stats.len(), 2);
+
+        let alice = get(&stats, "alice");
+        assert_eq!(alice.authored, 3);
+        assert_eq!(alice.html_url, "https://github.com/alice");
+
+        let carol = get(&stats, "carol");
+        assert_eq!(carol.authored, 1);
+        assert_eq!(carol.html_url, "mailto:Carol@Example.com");
+    }
+
+    #[test]
+    fn credits_co_authors_besides_the_author() {
+        let identities = Identities::parse("alice <alice@x> <alice@work.example>").unwrap();
+        let mut contributors = Contributors::new(identities, BotFilter::default());
+        let commit = commit(Some("alice"), "alice@x", 1);
+
+        // The author, by email or by an alias.
+        contributors.add_co_author("org/a", &commit, None, "ALICE@X");
+        contributors.add_co_author("org/a", &commit, None, "alice@work.example");
+        // Neither an account nor a known email.
+        contributors.add_co_author("org/a", &commit, None, "ghost@x");
+        contributors.add_co_author("org/a", &commit, Some(&user("bob")), "bob@x");
+
+        let stats = contributors.into_sorted(SortBy::Login);
+        assert_eq!(stats.len(), 1);
+        let bob = get(&stats, "bob");
+        assert_eq!((bob.authored, bob.co_authored), (0, 1));
+        assert_eq!(bob.first_commit, Some(day(1)));
+    }
+}
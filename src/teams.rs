@@ -0,0 +1,145 @@
+use eyre::Result;
+use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
+
+use crate::{client::ApiClient, Author};
+
+/// A team of an organization with the logins of its members.
+#[derive(Debug, Clone)]
+pub struct Team {
+    pub org: String,
+    pub slug: String,
+    pub name: String,
+    /// Members of the team and of its child teams
+    pub members: Vec<String>,
+    /// Empty unless the repositories were asked for
+    pub repos: Vec<TeamRepo>,
+}
+
+impl Team {
+    /// Whether `name` is the slug of this team, or its `org/slug`.
+    pub fn is_named(&self, name: &str) -> bool {
+        match name.split_once('/') {
+            Some((org, slug)) => org.eq_ignore_ascii_case(&self.org) && slug == self.slug,
+            None => name == self.slug,
+        }
+    }
+}
+
+/// A repository a team has access to.
+#[derive(Debug, Clone, Serialize)]
+pub struct TeamRepo {
+    pub full_name: String,
+    /// `admin`, `maintain`, `write`, `triage`, `read` or the name of a custom role
+    pub permission: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct GitHubTeam {
+    slug: String,
+    name: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct GitHubTeamRepo {
+    full_name: String,
+    /// Missing on older GitHub Enterprise Server releases
+    role_name: Option<String>,
+    #[serde(default)]
+    permissions: Permissions,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct Permissions {
+    #[serde(default)]
+    admin: bool,
+    #[serde(default)]
+    maintain: bool,
+    #[serde(default)]
+    push: bool,
+    #[serde(default)]
+    triage: bool,
+}
+
+impl GitHubTeamRepo {
+    fn permission(&self) -> String {
+        if let Some(role) = &self.role_name {
+            return role.clone();
+        }
+
+        let p = &self.permissions;
+        let role = if p.admin {
+            "admin"
+        } else if p.maintain {
+            "maintain"
+        } else if p.push {
+            "write"
+        } else if p.triage {
+            "triage"
+        } else {
+            "read"
+        };
+
+        role.to_string()
+    }
+}
+
+/// Collect the teams of `org` visible to the token with their members, and
+/// their repositories if `with_repos` is set.
+pub async fn get_teams(
+    gh: &ApiClient,
+    api_url: &str,
+    org: &str,
+    with_repos: bool,
+    pb: Option<&ProgressBar>,
+) -> Result<Vec<Team>> {
+    let teams = gh
+        .get_paginated::<GitHubTeam, _>(
+            format!("{api_url}/orgs/{org}/teams?per_page=100"),
+            pb,
+            |_| Ok(true),
+        )
+        .await?;
+
+    let mut res = vec![];
+
+    for team in teams {
+        let team_url = format!("{api_url}/orgs/{org}/teams/{}", team.slug);
+
+        let members = gh
+            .get_paginated::<Author, _>(format!("{team_url}/members?per_page=100"), pb, |_| {
+                Ok(true)
+            })
+            .await?
+            .into_iter()
+            .filter_map(|u| u.login)
+            .collect();
+
+        let repos = if with_repos {
+            gh.get_paginated::<GitHubTeamRepo, _>(
+                format!("{team_url}/repos?per_page=100"),
+                pb,
+                |_| Ok(true),
+            )
+            .await?
+            .into_iter()
+            .map(|r| TeamRepo {
+                permission: r.permission(),
+                full_name: r.full_name,
+            })
+            .collect()
+        } else {
+            vec![]
+        };
+
+        res.push(Team {
+            org: org.to_string(),
+            slug: team.slug,
+            name: team.name,
+            members,
+            repos,
+        });
+    }
+
+    Ok(res)
+}
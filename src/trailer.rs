@@ -0,0 +1,87 @@
+use clap::ValueEnum;
+
+/// Commit message trailers which credit someone besides the author.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trailer {
+    CoAuthoredBy,
+    SignedOffBy,
+    ReviewedBy,
+    TestedBy,
+}
+
+impl Trailer {
+    fn key(self) -> &'static str {
+        match self {
+            Trailer::CoAuthoredBy => "co-authored-by",
+            Trailer::SignedOffBy => "signed-off-by",
+            Trailer::ReviewedBy => "reviewed-by",
+            Trailer::TestedBy => "tested-by",
+        }
+    }
+}
+
+/// Emails of everyone credited by one of `trailers` in the last paragraph of
+/// `message`, each listed once.
+pub fn parse(message: &str, trailers: &[Trailer]) -> Vec<String> {
+    let mut res: Vec<String> = vec![];
+
+    let Some(last) = message.trim_end().rsplit("\n\n").next() else {
+        return res;
+    };
+
+    for line in last.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+
+        let key = key.trim().to_lowercase();
+        if !trailers.iter().any(|t| t.key() == key) {
+            continue;
+        }
+
+        // `Name <email>`
+        let Some(email) = value
+            .rsplit_once('<')
+            .and_then(|(_, email)| email.split_once('>'))
+            .map(|(email, _)| email.trim())
+            .filter(|email| !email.is_empty())
+        else {
+            continue;
+        };
+
+        if !res.iter().any(|e| e.eq_ignore_ascii_case(email)) {
+            res.push(email.to_string());
+        }
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSAGE: &str = "Fix the build
+
+Co-authored-by: Ignored <ignored@example.com>
+
+Co-Authored-By: Bob <bob@example.com>
+co-authored-by: bob <BOB@example.com>
+Co-authored-by: Nobody
+Signed-off-by: Carol <carol@example.com>
+";
+
+    #[test]
+    fn reads_the_last_paragraph() {
+        assert_eq!(
+            parse(MESSAGE, &[Trailer::CoAuthoredBy]),
+            ["bob@example.com"]
+        );
+        assert_eq!(
+            parse(MESSAGE, &[Trailer::CoAuthoredBy, Trailer::SignedOffBy]),
+            ["bob@example.com", "carol@example.com"]
+        );
+        assert!(parse(MESSAGE, &[Trailer::ReviewedBy]).is_empty());
+        assert!(parse("Fix the build", &[Trailer::CoAuthoredBy]).is_empty());
+    }
+}